@@ -2,15 +2,81 @@ use anyhow::*;
 use fs_extra::{copy_items, dir::CopyOptions};
 use glob::glob;
 use rayon::prelude::*;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
 use std::env;
-use std::fs::{read_to_string, write};
-use std::path::PathBuf;
+use std::fs::{read, read_to_string, write};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
 
+use permutation_manifest::{Defines, Permutation};
+
+#[path = "src/include.rs"]
+mod include;
+#[path = "build/interface.rs"]
+mod interface;
+#[path = "src/permutation_manifest.rs"]
+mod permutation_manifest;
+#[path = "src/spirv.rs"]
+mod spirv;
+#[path = "build/uniforms.rs"]
+mod uniforms;
+#[path = "src/vertex_layout.rs"]
+mod vertex_layout;
+#[path = "src/wgsl.rs"]
+mod wgsl;
+
+// Lets `vertex_layout.rs` name its types the same way in both crates.
+extern crate wgpu_types as wgpu;
+
+/// Root that `#include` directives are resolved against.
+const SHADER_DIR: &str = "src";
+/// Lists the define sets each shader is additionally compiled with.
+const PERMUTATIONS: &str = "src/permutations.txt";
+
+/// Vertex buffers each vertex shader is drawn with, mirroring the
+/// `create_render_pipeline` calls in `main.rs`.
+const VERTEX_BUFFERS: &[(&str, &[&[wgpu::VertexAttributeDescriptor]])] = &[
+    ("shader.vert", &[&vertex_layout::MODEL_VERTEX_ATTRIBUTES]),
+    ("light.vert", &[&vertex_layout::MODEL_VERTEX_ATTRIBUTES]),
+];
+
+/// Everything handed to shaderc besides the source itself. It is part of the
+/// cache key, so changing a setting recompiles every shader.
+#[derive(Hash)]
+struct CompileSettings {
+    optimize: bool,
+    generate_debug_info: bool,
+}
+
+impl CompileSettings {
+    fn options(&self, defines: &Defines) -> Result<shaderc::CompileOptions> {
+        let mut options =
+            shaderc::CompileOptions::new().context("Unable to create compile options")?;
+        if self.optimize {
+            options.set_optimization_level(shaderc::OptimizationLevel::Performance);
+        }
+        if self.generate_debug_info {
+            options.set_generate_debug_info();
+        }
+        options.set_include_callback(|requested, _, _, _| {
+            include::resolve(Path::new(SHADER_DIR), requested)
+        });
+        for (name, value) in defines {
+            options.add_macro_definition(name, value.as_deref());
+        }
+        Ok(options)
+    }
+}
+
+#[derive(Clone)]
 struct ShaderData {
     src: String,
     src_path: PathBuf,
     spv_path: PathBuf,
     kind: shaderc::ShaderKind,
+    includes: Vec<PathBuf>,
+    defines: Defines,
 }
 
 impl ShaderData {
@@ -28,6 +94,8 @@ impl ShaderData {
         };
 
         let src = read_to_string(src_path.clone())?;
+        let includes = include::dependencies(Path::new(SHADER_DIR), &src)
+            .with_context(|| format!("Unresolved include in {}", src_path.display()))?;
         let spv_path = src_path.with_extension(format!("{}.sprv", extension));
 
         Ok(Self {
@@ -35,51 +103,283 @@ impl ShaderData {
             src_path,
             spv_path,
             kind,
+            includes,
+            defines: Vec::new(),
         })
     }
+
+    /// The same source compiled with extra defines into `<shader>.<variant>.sprv`.
+    pub fn permutation(&self, permutation: &Permutation) -> Self {
+        let mut spv_name = self.src_path.file_name().unwrap_or_default().to_owned();
+        spv_name.push(".");
+        spv_name.push(&permutation.variant);
+        spv_name.push(".sprv");
+
+        Self {
+            spv_path: self.spv_path.with_file_name(spv_name),
+            defines: permutation.defines.clone(),
+            ..self.clone()
+        }
+    }
+
+    /// The source path, followed by the defines of a permutation.
+    pub fn name(&self) -> String {
+        let mut name = self.src_path.display().to_string();
+        for (define, value) in &self.defines {
+            name += &format!(" {}", define);
+            if let Some(value) = value {
+                name += &format!("={}", value);
+            }
+        }
+        name
+    }
+
+    /// Hashes the source, the contents of everything it includes and the
+    /// compile settings. Equal keys produce identical SPIR-V.
+    pub fn cache_key(&self, settings: &CompileSettings) -> Result<u64> {
+        let mut hasher = DefaultHasher::new();
+        self.src.hash(&mut hasher);
+        for include in &self.includes {
+            read_to_string(include)?.hash(&mut hasher);
+        }
+        self.defines.hash(&mut hasher);
+        settings.hash(&mut hasher);
+        Ok(hasher.finish())
+    }
+}
+
+fn camel_case(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase())
+                .into_iter()
+                .chain(chars)
+        })
+        .collect()
+}
+
+/// Emits one enum per permuted shader, e.g. `ShaderFrag`, whose variants know
+/// their defines and where their SPIR-V is.
+fn generate_permutations(permutations: &[Permutation]) -> String {
+    let mut shaders = Vec::<&str>::new();
+    for permutation in permutations {
+        if !shaders.contains(&permutation.shader.as_str()) {
+            shaders.push(&permutation.shader);
+        }
+    }
+
+    let mut code = String::new();
+    for shader in shaders {
+        let ty = camel_case(shader);
+        let variants = permutations
+            .iter()
+            .filter(|permutation| permutation.shader == shader)
+            .map(|permutation| (camel_case(&permutation.variant), permutation))
+            .collect::<Vec<_>>();
+
+        code += "#[derive(Debug, Copy, Clone, PartialEq, Eq)]\n";
+        code += &format!("pub enum {} {{\n", ty);
+        for (variant, _) in &variants {
+            code += &format!("    {},\n", variant);
+        }
+        code += "}\n\n";
+
+        code += &format!("impl {} {{\n", ty);
+        code += &format!("    pub const SOURCE: &'static str = {:?};\n", shader);
+        code += "    pub const ALL: &'static [Self] = &[\n";
+        for (variant, _) in &variants {
+            code += &format!("        Self::{},\n", variant);
+        }
+        code += "    ];\n\n";
+
+        code += "    pub fn defines(self) -> &'static [(&'static str, Option<&'static str>)] {\n";
+        code += "        match self {\n";
+        for (variant, permutation) in &variants {
+            let defines = permutation
+                .defines
+                .iter()
+                .map(|(name, value)| format!("({:?}, {:?})", name, value.as_deref()))
+                .collect::<Vec<_>>()
+                .join(", ");
+            code += &format!("            Self::{} => &[{}],\n", variant, defines);
+        }
+        code += "        }\n    }\n\n";
+
+        code += "    pub fn spirv(self) -> wgpu::ShaderModuleSource<'static> {\n";
+        code += "        match self {\n";
+        for (variant, permutation) in &variants {
+            code += &format!(
+                "            Self::{} => wgpu::include_spirv!(concat!(env!(\"CARGO_MANIFEST_DIR\"), \"/{}/{}.{}.sprv\")),\n",
+                variant, SHADER_DIR, shader, permutation.variant
+            );
+        }
+        code += "        }\n    }\n}\n\n";
+    }
+    code
+}
+
+/// Maps each `.sprv` path to the key of the source it was compiled from.
+struct ShaderCache {
+    path: PathBuf,
+    entries: HashMap<PathBuf, u64>,
+}
+
+impl ShaderCache {
+    fn load(path: PathBuf) -> Self {
+        let entries = read_to_string(&path)
+            .map(|cache| {
+                cache
+                    .lines()
+                    .filter_map(|line| {
+                        let mut parts = line.splitn(2, ' ');
+                        let key = u64::from_str_radix(parts.next()?, 16).ok()?;
+                        Some((PathBuf::from(parts.next()?), key))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self { path, entries }
+    }
+
+    fn is_fresh(&self, spv_path: &Path, key: u64) -> bool {
+        self.entries.get(spv_path) == Some(&key) && spv_path.exists()
+    }
+
+    fn insert(&mut self, spv_path: PathBuf, key: u64) {
+        self.entries.insert(spv_path, key);
+    }
+
+    fn save(&self) -> Result<()> {
+        let cache = self
+            .entries
+            .iter()
+            .map(|(spv_path, key)| format!("{:016x} {}\n", key, spv_path.display()))
+            .collect::<String>();
+        write(&self.path, cache)?;
+        Ok(())
+    }
 }
 
 fn main() -> Result<()> {
-    // This tells cargo to rerun this script if something in /src/ changes.
-    println!("cargo:rerun-if-changed=src/*");
+    let out_dir = env::var("OUT_DIR")?;
 
     let mut shader_paths = Vec::new();
-    shader_paths.extend(glob(".src/**/*.vert")?);
-    shader_paths.extend(glob(".src/**/*.frag")?);
-    shader_paths.extend(glob(".src/**/*.comp")?);
-    // let mut shader_paths = vec![
-    //     glob("./src/**/*.vert")?,
-    //     glob("./src/**/*.frag")?,
-    //     glob("./src/**/*.comp")?,
-    // ];
-
-    // This could be parallelized
-    let shaders = shader_paths
+    shader_paths.extend(glob(&format!("{}/**/*.vert", SHADER_DIR))?);
+    shader_paths.extend(glob(&format!("{}/**/*.frag", SHADER_DIR))?);
+    shader_paths.extend(glob(&format!("{}/**/*.comp", SHADER_DIR))?);
+
+    let mut shaders = shader_paths
         .into_par_iter()
         .map(|glob_result| ShaderData::load(glob_result?))
-        .collect::<Result<Vec<_>>>();
+        .collect::<Result<Vec<_>>>()?;
 
+    for wgsl_path in glob(&format!("{}/**/*.wgsl", SHADER_DIR))? {
+        let wgsl_path = wgsl_path?;
+        println!("cargo:rerun-if-changed={}", wgsl_path.display());
+        // WGSL isn't compiled ahead of time, wgpu takes the source as is.
+        // Validating it here makes mistakes build errors rather than a panic
+        // inside wgpu once the shader module gets created.
+        wgsl::validate(&wgsl_path, &read_to_string(&wgsl_path)?)?;
+    }
+
+    println!("cargo:rerun-if-changed={}", PERMUTATIONS);
+    let permutations = permutation_manifest::load(PERMUTATIONS)?;
+    for permutation in &permutations {
+        let src_path = Path::new(SHADER_DIR).join(&permutation.shader);
+        let base = shaders
+            .iter()
+            .find(|shader| shader.src_path == src_path)
+            .with_context(|| format!("{}: Unknown shader {}", PERMUTATIONS, permutation.shader))?;
+        let variant = base.permutation(permutation);
+        shaders.push(variant);
+    }
+    write(
+        Path::new(&out_dir).join("permutations.rs"),
+        generate_permutations(&permutations),
+    )?;
+
+    let settings = CompileSettings {
+        optimize: env::var("PROFILE")? == "release",
+        generate_debug_info: false,
+    };
+
+    let mut cache = ShaderCache::load(Path::new(&out_dir).join("shader_cache"));
     let mut compiler = shaderc::Compiler::new().context("Unable to create shader compiler")?;
 
-    // This can't be parallelized. The [shaderc::Compiler] is not
-    // thread safe. Also, it creates a lot of resources. You could
-    // spawn multiple processes to handle this, but it would probably
-    // be better just to only compile shaders that have been changed
-    // recently.
-    for shader in shaders? {
+    // The [shaderc::Compiler] is not thread safe, so compilation stays
+    // sequential. Shaders whose cache key didn't change since the last
+    // build are skipped altogether.
+    for shader in &shaders {
+        println!("cargo:rerun-if-changed={}", shader.src_path.display());
+        for include in &shader.includes {
+            println!("cargo:rerun-if-changed={}", include.display());
+        }
+
+        let key = shader.cache_key(&settings)?;
+        if cache.is_fresh(&shader.spv_path, key) {
+            continue;
+        }
+
+        let options = settings.options(&shader.defines)?;
         let compiled = compiler.compile_into_spirv(
             &shader.src,
             shader.kind,
             &shader.src_path.to_str().unwrap(),
             "main",
-            None,
+            Some(&options),
         )?;
-        write(shader.spv_path, compiled.as_binary_u8())?;
+        write(&shader.spv_path, compiled.as_binary_u8())?;
+        cache.insert(shader.spv_path.clone(), key);
+    }
+    cache.save()?;
+
+    let modules = shaders
+        .iter()
+        .map(|shader| {
+            let module = spirv::parse(&spirv::words(&read(&shader.spv_path)?)?)
+                .with_context(|| format!("Unable to reflect {}", shader.spv_path.display()))?;
+            Ok((shader, module))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let base_module = |src_path: &Path| {
+        modules
+            .iter()
+            .find(|(shader, _)| shader.src_path == src_path && shader.defines.is_empty())
+    };
+
+    for (fs, fs_module) in &modules {
+        if fs.kind != shaderc::ShaderKind::Fragment {
+            continue;
+        }
+        if let Some((vs, vs_module)) = base_module(&fs.src_path.with_extension("vert")) {
+            interface::check_stages(&vs.name(), vs_module, &fs.name(), fs_module)?;
+        }
+    }
+    for (vs_name, buffers) in VERTEX_BUFFERS {
+        let (vs, vs_module) = base_module(&Path::new(SHADER_DIR).join(vs_name))
+            .with_context(|| format!("Vertex buffers are listed for missing {}", vs_name))?;
+        interface::check_vertex_buffers(&vs.name(), vs_module, buffers)?;
     }
 
+    // Permutations share their blocks with the base shader, so only the
+    // latter is looked at.
+    let mut generator = uniforms::Generator::default();
+    for (shader, module) in &modules {
+        if shader.defines.is_empty() {
+            generator.add_module(&shader.src_path, module)?;
+        }
+    }
+    write(
+        Path::new(&out_dir).join("uniforms.rs"),
+        generator.generate()?,
+    )?;
+
     println!("cargo:rerun-if-changed=res/*");
 
-    let out_dir = env::var("OUT_DIR")?;
     let mut copy_options = CopyOptions::new();
     copy_options.overwrite = true;
     let mut paths_to_copy = Vec::new();
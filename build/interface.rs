@@ -0,0 +1,137 @@
+//! Checks that connected pipeline stages agree on their interface, so a
+//! mismatch fails the build instead of rendering garbage.
+
+use crate::spirv::{Module, Type, Variable};
+use anyhow::*;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Scalar {
+    Float,
+    Sint,
+    Uint,
+}
+
+fn scalar(ty: &Type) -> Option<(Scalar, u32)> {
+    match ty {
+        Type::Float { width: 32 } => Some((Scalar::Float, 1)),
+        Type::Int {
+            width: 32,
+            signed: true,
+        } => Some((Scalar::Sint, 1)),
+        Type::Int {
+            width: 32,
+            signed: false,
+        } => Some((Scalar::Uint, 1)),
+        Type::Vector { component, count } => scalar(component).map(|(s, _)| (s, *count)),
+        _ => None,
+    }
+}
+
+fn format_scalar(format: &wgpu::VertexFormat) -> (Scalar, u32) {
+    use wgpu::VertexFormat::*;
+    match format {
+        Uchar2Norm | Char2Norm | Ushort2Norm | Short2Norm | Half2 | Float2 => (Scalar::Float, 2),
+        Uchar4Norm | Char4Norm | Ushort4Norm | Short4Norm | Half4 | Float4 => (Scalar::Float, 4),
+        Float => (Scalar::Float, 1),
+        Float3 => (Scalar::Float, 3),
+        Uchar2 | Ushort2 | Uint2 => (Scalar::Uint, 2),
+        Uchar4 | Ushort4 | Uint4 => (Scalar::Uint, 4),
+        Uint => (Scalar::Uint, 1),
+        Uint3 => (Scalar::Uint, 3),
+        Char2 | Short2 | Int2 => (Scalar::Sint, 2),
+        Char4 | Short4 | Int4 => (Scalar::Sint, 4),
+        Int => (Scalar::Sint, 1),
+        Int3 => (Scalar::Sint, 3),
+    }
+}
+
+/// Spells a type the way GLSL does, for error messages.
+fn glsl_name(ty: &Type) -> String {
+    match scalar(ty) {
+        Some((Scalar::Float, 1)) => "float".into(),
+        Some((Scalar::Sint, 1)) => "int".into(),
+        Some((Scalar::Uint, 1)) => "uint".into(),
+        Some((Scalar::Float, n)) => format!("vec{}", n),
+        Some((Scalar::Sint, n)) => format!("ivec{}", n),
+        Some((Scalar::Uint, n)) => format!("uvec{}", n),
+        None => match ty {
+            Type::Matrix { column, columns } => match **column {
+                Type::Vector { count, .. } if count == *columns => format!("mat{}", columns),
+                Type::Vector { count, .. } => format!("mat{}x{}", columns, count),
+                _ => format!("{:?}", ty),
+            },
+            _ => format!("{:?}", ty),
+        },
+    }
+}
+
+fn location(variable: &Variable, shader: &str) -> Result<u32> {
+    variable
+        .location
+        .with_context(|| format!("{}: `{}` has no location", shader, variable.name))
+}
+
+/// Every input the fragment shader reads must be written by the vertex
+/// shader at the same location and with the same type.
+pub fn check_stages(vs_name: &str, vs: &Module, fs_name: &str, fs: &Module) -> Result<()> {
+    for input in fs.inputs() {
+        let at = location(input, fs_name)?;
+        let output = vs.outputs().find(|output| output.location == Some(at));
+        match output {
+            None => bail!(
+                "{} reads `{}` at location {}, but {} never writes location {}",
+                fs_name,
+                input.name,
+                at,
+                vs_name,
+                at
+            ),
+            Some(output) if output.ty != input.ty => bail!(
+                "Location {} is written as {} `{}` by {}, but read as {} `{}` by {}",
+                at,
+                glsl_name(&output.ty),
+                output.name,
+                vs_name,
+                glsl_name(&input.ty),
+                input.name,
+                fs_name
+            ),
+            Some(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// Every vertex shader input must be fed by an attribute with the same
+/// location and a matching format.
+pub fn check_vertex_buffers(
+    vs_name: &str,
+    vs: &Module,
+    buffers: &[&[wgpu::VertexAttributeDescriptor]],
+) -> Result<()> {
+    for input in vs.inputs() {
+        let at = location(input, vs_name)?;
+        let attribute = buffers
+            .iter()
+            .flat_map(|attributes| attributes.iter())
+            .find(|attribute| attribute.shader_location == at)
+            .with_context(|| {
+                format!(
+                    "{} reads `{}` at location {}, but no vertex attribute provides it",
+                    vs_name, input.name, at
+                )
+            })?;
+
+        let expected = format_scalar(&attribute.format);
+        ensure!(
+            scalar(&input.ty) == Some(expected),
+            "{} reads `{}` at location {} as {}, but the vertex attribute is {:?}",
+            vs_name,
+            input.name,
+            at,
+            glsl_name(&input.ty),
+            attribute.format
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,183 @@
+//! Generates `#[repr(C)]` Rust mirrors of the uniform and storage blocks the
+//! shaders declare, using the offsets the compiler picked for std140/std430.
+
+use crate::spirv::{Member, Module, Type};
+use anyhow::*;
+use std::path::Path;
+
+struct Block<'a> {
+    name: &'a str,
+    ty: &'a Type,
+    declared_in: &'a Path,
+}
+
+#[derive(Default)]
+pub struct Generator<'a> {
+    blocks: Vec<Block<'a>>,
+    generated: Vec<(String, u32)>,
+    code: String,
+}
+
+impl<'a> Generator<'a> {
+    /// Collects the blocks `module` binds. Blocks with a runtime sized array,
+    /// such as the instance storage buffer, have no fixed Rust layout and are
+    /// skipped.
+    pub fn add_module(&mut self, declared_in: &'a Path, module: &'a Module) -> Result<()> {
+        for variable in module.resources() {
+            if !variable.is_uniform_buffer() && !variable.is_storage_buffer() {
+                continue;
+            }
+            if let Type::Struct { name, members, .. } = &variable.ty {
+                if members
+                    .iter()
+                    .any(|m| matches!(m.ty, Type::RuntimeArray { .. }))
+                {
+                    continue;
+                }
+                self.add_block(name, &variable.ty, declared_in)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn add_block(&mut self, name: &'a str, ty: &'a Type, declared_in: &'a Path) -> Result<()> {
+        match self.blocks.iter().find(|block| block.name == name) {
+            Some(block) if block.ty != ty => bail!(
+                "Block `{}` is declared differently in {} and {}",
+                name,
+                block.declared_in.display(),
+                declared_in.display()
+            ),
+            Some(_) => Ok(()),
+            None => {
+                self.blocks.push(Block {
+                    name,
+                    ty,
+                    declared_in,
+                });
+                Ok(())
+            }
+        }
+    }
+
+    pub fn generate(mut self) -> Result<String> {
+        let blocks = std::mem::take(&mut self.blocks);
+        for block in &blocks {
+            if let Type::Struct { members, .. } = block.ty {
+                self.generate_struct(block.name, members)
+                    .with_context(|| format!("In {}", block.declared_in.display()))?;
+            }
+        }
+        Ok(self.code)
+    }
+
+    /// Writes the struct and returns its size in bytes.
+    fn generate_struct(&mut self, name: &str, members: &[Member]) -> Result<u32> {
+        if let Some((_, size)) = self.generated.iter().find(|(done, _)| done == name) {
+            return Ok(*size);
+        }
+
+        let mut fields = String::new();
+        let mut params = Vec::new();
+        let mut padding = 0;
+        let mut end = 0;
+
+        for member in members {
+            let offset = member
+                .offset
+                .with_context(|| format!("{}::{} has no offset", name, member.name))?;
+            let (ty, size) = self
+                .rust_type(&member.ty, member)
+                .with_context(|| format!("Unsupported type for {}::{}", name, member.name))?;
+
+            ensure!(offset >= end, "{}::{} overlaps", name, member.name);
+            if offset > end {
+                fields += &format!("    _padding{}: [u8; {}],\n", padding, offset - end);
+                padding += 1;
+            }
+            fields += &format!("    pub {}: {},\n", member.name, ty);
+            params.push((member.name.as_str(), ty));
+            end = offset + size;
+        }
+
+        let args = params
+            .iter()
+            .map(|(name, ty)| format!("{}: {}", name, ty))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let inits = params
+            .iter()
+            .map(|(name, _)| format!("            {},\n", name))
+            .collect::<String>();
+
+        // Fields keep their GLSL names, which need not be snake case
+        self.code += "#[repr(C)]\n";
+        self.code += "#[derive(Debug, Copy, Clone)]\n";
+        self.code += "#[allow(dead_code, non_snake_case)]\n";
+        self.code += &format!("pub struct {} {{\n{}}}\n\n", name, fields);
+        self.code += "#[allow(dead_code, non_snake_case)]\n";
+        self.code += &format!("impl {} {{\n", name);
+        self.code += &format!("    pub fn new({}) -> Self {{\n", args);
+        self.code += "        Self {\n";
+        self.code += &inits;
+        if padding > 0 {
+            self.code += "            ..bytemuck::Zeroable::zeroed()\n";
+        }
+        self.code += "        }\n    }\n}\n\n";
+        self.code += &format!("unsafe impl bytemuck::Zeroable for {} {{}}\n", name);
+        self.code += &format!("unsafe impl bytemuck::Pod for {} {{}}\n\n", name);
+
+        self.generated.push((name.to_owned(), end));
+        Ok(end)
+    }
+
+    fn rust_type(&mut self, ty: &Type, member: &Member) -> Result<(String, u32)> {
+        Ok(match ty {
+            Type::Bool => ("u32".into(), 4),
+            Type::Int { width: 32, signed } => ((if *signed { "i32" } else { "u32" }).into(), 4),
+            Type::Float { width: 32 } => ("f32".into(), 4),
+            Type::Vector { component, count } => match **component {
+                Type::Float { width: 32 } => (format!("ultraviolet::Vec{}", count), 4 * count),
+                _ => {
+                    let (scalar, _) = self.rust_type(component, member)?;
+                    (format!("[{}; {}]", scalar, count), 4 * count)
+                }
+            },
+            Type::Matrix { column, columns } => {
+                ensure!(!member.row_major, "Row major matrices are not supported");
+                let rows = match **column {
+                    Type::Vector { count, .. } => count,
+                    _ => bail!("Matrix columns are not vectors"),
+                };
+                match member.matrix_stride.unwrap_or(4 * rows) {
+                    stride if stride == 4 * rows && rows == *columns => {
+                        (format!("ultraviolet::Mat{}", rows), stride * columns)
+                    }
+                    16 => (format!("[ultraviolet::Vec4; {}]", columns), 16 * columns),
+                    stride => bail!("Matrix stride {} is not supported", stride),
+                }
+            }
+            Type::Array {
+                element,
+                length,
+                stride,
+            } => {
+                let (element, size) = self.rust_type(element, member)?;
+                let stride = stride.unwrap_or(size);
+                ensure!(
+                    stride == size,
+                    "Array stride {} differs from the element size {}",
+                    stride,
+                    size
+                );
+                (format!("[{}; {}]", element, length), stride * length)
+            }
+            Type::Struct { name, members, .. } => {
+                ensure!(!name.is_empty(), "Nested struct has no name");
+                let size = self.generate_struct(name, members)?;
+                (name.clone(), size)
+            }
+            _ => bail!("{:?}", ty),
+        })
+    }
+}
@@ -1,3 +1,10 @@
+//! Angles that know their unit. Arithmetic between `Rad` and `Deg` converts
+//! the right hand side to the unit of the left.
+
+use std::f32::consts::PI;
+use std::fmt;
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
 #[derive(Debug, Copy, Clone, PartialOrd, PartialEq)]
 pub struct Rad(pub f32);
 
@@ -6,13 +13,13 @@ pub struct Deg(pub f32);
 
 impl From<Rad> for Deg {
     fn from(x: Rad) -> Self {
-        Self(180.0 * x.0 / std::f32::consts::PI)
+        Self(180.0 * x.0 / PI)
     }
 }
 
 impl From<Deg> for Rad {
     fn from(x: Deg) -> Self {
-        Self(std::f32::consts::PI * x.0 / 180.0)
+        Self(PI * x.0 / 180.0)
     }
 }
 
@@ -40,36 +47,271 @@ impl From<Deg> for f32 {
     }
 }
 
-impl std::ops::Neg for Rad {
-    type Output = Self;
-    fn neg(self) -> Self::Output {
-        Self(-self.0)
+impl fmt::Display for Rad {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)?;
+        f.write_str(" rad")
     }
 }
 
-impl std::ops::Neg for Deg {
-    type Output = Self;
-    fn neg(self) -> Self::Output {
-        Self(-self.0)
+impl fmt::Display for Deg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)?;
+        f.write_str("°")
     }
 }
 
-impl std::ops::Mul<f32> for Rad {
-    type Output = Self;
-    fn mul(self, rhs: f32) -> Self::Output {
-        Self(self.0 * rhs)
-    }
+/// `Rad` or `Deg`. Methods that take an angle in either unit take an
+/// `Angle` rather than an `Into<Rad>`, which would let bare floats through.
+pub trait Angle: Copy + Into<Rad> + Into<Deg> {}
+
+impl Angle for Rad {}
+
+impl Angle for Deg {}
+
+/// What `Rad` and `Deg` have in common, `$turn` being a full turn in the
+/// unit of `$angle`.
+macro_rules! impl_angle {
+    ($angle:ident, $other:ident, $turn:expr) => {
+        // Both units get the whole set, whichever of them the scene uses
+        #[allow(dead_code)]
+        impl $angle {
+            /// A full turn.
+            pub const TURN: Self = Self($turn);
+
+            pub fn sin(self) -> f32 {
+                Rad::from(self).0.sin()
+            }
+
+            pub fn cos(self) -> f32 {
+                Rad::from(self).0.cos()
+            }
+
+            pub fn tan(self) -> f32 {
+                Rad::from(self).0.tan()
+            }
+
+            pub fn sin_cos(self) -> (f32, f32) {
+                Rad::from(self).0.sin_cos()
+            }
+
+            /// The same direction, as an angle from 0 up to a full turn.
+            pub fn wrapped(self) -> Self {
+                let wrapped = self.0.rem_euclid($turn);
+                // Tiny negative angles round up to a whole turn
+                Self(if wrapped < $turn { wrapped } else { 0.0 })
+            }
+
+            /// The same direction, as an angle from just over minus half a
+            /// turn up to half a turn.
+            pub fn wrapped_signed(self) -> Self {
+                let half = $turn / 2.0;
+                let wrapped = (half - self.0).rem_euclid($turn);
+                Self(if wrapped < $turn {
+                    half - wrapped
+                } else {
+                    half
+                })
+            }
+
+            /// The smallest turn that takes this direction to `to`, positive
+            /// or negative and never more than half a turn.
+            pub fn shortest_arc_to<A: Angle>(self, to: A) -> Self {
+                (Into::<Self>::into(to) - self).wrapped_signed()
+            }
+
+            /// The angle `t` of the way to `to`, turning the shortest way
+            /// round, so from 170° to -170° passes through 180° and not 0°.
+            pub fn lerp<A: Angle>(self, to: A, t: f32) -> Self {
+                self + self.shortest_arc_to(to) * t
+            }
+
+            pub fn clamp<A: Angle, B: Angle>(self, min: A, max: B) -> Self {
+                let (min, max): (Self, Self) = (min.into(), max.into());
+                Self(self.0.max(min.0).min(max.0))
+            }
+        }
+
+        impl Neg for $angle {
+            type Output = Self;
+            fn neg(self) -> Self::Output {
+                Self(-self.0)
+            }
+        }
+
+        impl Mul<f32> for $angle {
+            type Output = Self;
+            fn mul(self, rhs: f32) -> Self::Output {
+                Self(self.0 * rhs)
+            }
+        }
+
+        impl Div<f32> for $angle {
+            type Output = Self;
+            fn div(self, rhs: f32) -> Self::Output {
+                Self(self.0 / rhs)
+            }
+        }
+
+        impl Add<$angle> for $angle {
+            type Output = Self;
+            fn add(self, rhs: $angle) -> Self::Output {
+                Self(self.0 + rhs.0)
+            }
+        }
+
+        impl Sub<$angle> for $angle {
+            type Output = Self;
+            fn sub(self, rhs: $angle) -> Self::Output {
+                Self(self.0 - rhs.0)
+            }
+        }
+
+        impl AddAssign<$angle> for $angle {
+            fn add_assign(&mut self, rhs: $angle) {
+                self.0 += rhs.0;
+            }
+        }
+
+        impl SubAssign<$angle> for $angle {
+            fn sub_assign(&mut self, rhs: $angle) {
+                self.0 -= rhs.0;
+            }
+        }
+
+        impl Add<$other> for $angle {
+            type Output = Self;
+            fn add(self, rhs: $other) -> Self::Output {
+                self + Self::from(rhs)
+            }
+        }
+
+        impl Sub<$other> for $angle {
+            type Output = Self;
+            fn sub(self, rhs: $other) -> Self::Output {
+                self - Self::from(rhs)
+            }
+        }
+
+        impl AddAssign<$other> for $angle {
+            fn add_assign(&mut self, rhs: $other) {
+                *self += Self::from(rhs);
+            }
+        }
+
+        impl SubAssign<$other> for $angle {
+            fn sub_assign(&mut self, rhs: $other) {
+                *self -= Self::from(rhs);
+            }
+        }
+
+        impl PartialEq<$other> for $angle {
+            fn eq(&self, other: &$other) -> bool {
+                *self == Self::from(*other)
+            }
+        }
+
+        impl PartialOrd<$other> for $angle {
+            fn partial_cmp(&self, other: &$other) -> Option<std::cmp::Ordering> {
+                self.partial_cmp(&Self::from(*other))
+            }
+        }
+    };
 }
 
-impl std::ops::Mul<f32> for Deg {
-    type Output = Self;
-    fn mul(self, rhs: f32) -> Self::Output {
-        Self(self.0 * rhs)
+impl_angle!(Rad, Deg, 2.0 * PI);
+impl_angle!(Deg, Rad, 360.0);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close<A: Angle + fmt::Debug>(a: A, b: A) {
+        let (x, y): (Rad, Rad) = (a.into(), b.into());
+        assert!((x.0 - y.0).abs() < 1e-5, "{:?} is not {:?}", a, b);
+    }
+
+    #[test]
+    fn wrapped() {
+        assert_close(Rad(Rad::TURN.0 + 1.0).wrapped(), Rad(1.0));
+        assert_close(Rad(-1.0).wrapped(), Rad(Rad::TURN.0 - 1.0));
+        assert_close(Rad(-3.0 * Rad::TURN.0).wrapped(), Rad(0.0));
+        // Would round to a whole turn
+        assert_eq!(Rad(-1e-8).wrapped(), Rad(0.0));
+    }
+
+    #[test]
+    fn wrapped_degrees() {
+        assert_close(Deg(370.0).wrapped(), Deg(10.0));
+        assert_close(Deg(-90.0).wrapped(), Deg(270.0));
+        assert_eq!(Deg(-720.0).wrapped(), Deg(0.0));
+        assert_eq!(Deg(-1e-6).wrapped(), Deg(0.0));
+    }
+
+    #[test]
+    fn wrapped_signed() {
+        assert_close(Rad(3.0 * PI / 2.0).wrapped_signed(), Rad(-PI / 2.0));
+        assert_close(Rad(-PI / 2.0).wrapped_signed(), Rad(-PI / 2.0));
+        assert_eq!(Rad(PI).wrapped_signed(), Rad(PI));
+        assert_eq!(Rad(-PI).wrapped_signed(), Rad(PI));
+        assert_close(Rad(-1e-8).wrapped_signed(), Rad(0.0));
+        assert_close(Rad::TURN.wrapped_signed(), Rad(0.0));
+    }
+
+    #[test]
+    fn wrapped_signed_degrees() {
+        assert_close(Deg(270.0).wrapped_signed(), Deg(-90.0));
+        assert_close(Deg(-190.0).wrapped_signed(), Deg(170.0));
+        assert_eq!(Deg(180.0).wrapped_signed(), Deg(180.0));
+        assert_eq!(Deg(-180.0).wrapped_signed(), Deg(180.0));
+        assert_close(Deg(-1e-6).wrapped_signed(), Deg(0.0));
+    }
+
+    #[test]
+    fn shortest_arc_to() {
+        let from = Rad::from(Deg(170.0));
+        assert_close(from.shortest_arc_to(Deg(-170.0)), Deg(20.0).into());
+        assert_close(
+            Rad::from(Deg(-170.0)).shortest_arc_to(Deg(170.0)),
+            Deg(-20.0).into(),
+        );
+        assert_close(from.shortest_arc_to(from + Deg(720.0)), Rad(0.0));
+        // Half a turn either way comes out positive
+        assert_close(Rad(0.0).shortest_arc_to(Rad(-PI)), Rad(PI));
+    }
+
+    #[test]
+    fn shortest_arc_to_degrees() {
+        assert_close(Deg(170.0).shortest_arc_to(Deg(-170.0)), Deg(20.0));
+        assert_close(Deg(-170.0).shortest_arc_to(Rad(PI)), Deg(-10.0));
+        assert_close(Deg(10.0).shortest_arc_to(Deg(730.0)), Deg(0.0));
+        assert_close(Deg(0.0).shortest_arc_to(Deg(-180.0)), Deg(180.0));
+    }
+
+    #[test]
+    fn lerp() {
+        let from = Rad::from(Deg(170.0));
+        assert_close(from.lerp(Deg(-170.0), 0.5).wrapped(), Rad(PI));
+        assert_close(from.lerp(Deg(-170.0), 0.0), from);
+        assert_close(from.lerp(Deg(-170.0), 1.0), Deg(190.0).into());
+        assert_close(Rad(0.5).lerp(Rad(1.5), 0.25), Rad(0.75));
+    }
+
+    #[test]
+    fn lerp_degrees() {
+        assert_close(Deg(170.0).lerp(Deg(-170.0), 0.5), Deg(180.0));
+        assert_close(Deg(-170.0).lerp(Deg(170.0), 0.25), Deg(-175.0));
+        assert_close(Deg(10.0).lerp(Rad::from(Deg(50.0)), 0.5), Deg(30.0));
     }
-}
 
-impl std::ops::AddAssign<Rad> for Rad {
-    fn add_assign(&mut self, rhs: Rad) {
-        self.0 = self.0 + rhs.0;
+    #[test]
+    fn trig_and_clamp_agree_across_units() {
+        let (rad, deg) = (Rad::from(Deg(30.0)), Deg(30.0));
+        assert!((rad.sin() - deg.sin()).abs() < 1e-6);
+        assert!((rad.cos() - deg.cos()).abs() < 1e-6);
+        assert!((rad.tan() - deg.tan()).abs() < 1e-6);
+        assert!((deg.sin() - 0.5).abs() < 1e-6);
+        assert_close(Deg(100.0).clamp(Deg(-90.0), Rad(PI / 2.0)), Deg(90.0));
+        assert_close(Rad(-2.0).clamp(Deg(-90.0), Deg(90.0)), Rad(-PI / 2.0));
     }
 }
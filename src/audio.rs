@@ -0,0 +1,202 @@
+//! WAV files as playground channels, laid out the way Shadertoy's sound inputs
+//! are: a 512x2 texture holding the spectrum in its first row and the
+//! waveform in its second, both taken at the current playback position.
+//!
+//! The track loops from when it is loaded, mixed down to mono. With the
+//! `playback` feature it plays out loud and the texture follows what is
+//! being heard. Without it the track runs silently on the wall clock.
+
+use anyhow::*;
+#[cfg(feature = "playback")]
+use rodio::Source;
+use std::f32::consts::PI;
+use std::path::Path;
+use std::time::Instant;
+
+use crate::texture;
+
+const WIDTH: usize = 512;
+/// Samples per transform, giving `WIDTH` frequency bins.
+const FFT_SIZE: usize = 2 * WIDTH;
+
+// Shadertoy analyzes its audio with WebAudio's `AnalyserNode`; these are its
+// defaults.
+const MIN_DECIBELS: f32 = -100.0;
+const MAX_DECIBELS: f32 = -30.0;
+const SMOOTHING: f32 = 0.8;
+
+/// In-place radix-2 FFT. The length must be a power of two.
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let step = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (step * k as f32).sin_cos();
+                let a = start + k;
+                let b = a + len / 2;
+                let tr = re[b] * cos - im[b] * sin;
+                let ti = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - tr;
+                im[b] = im[a] - ti;
+                re[a] += tr;
+                im[a] += ti;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+fn blackman(i: usize, n: usize) -> f32 {
+    let x = 2.0 * PI * i as f32 / n as f32;
+    0.42 - 0.5 * x.cos() + 0.08 * (2.0 * x).cos()
+}
+
+/// Keeps the track playing until dropped.
+#[cfg(feature = "playback")]
+struct Playback {
+    // The sink goes quiet once the stream it plays to is gone
+    _stream: rodio::OutputStream,
+    _sink: rodio::Sink,
+}
+
+#[cfg(feature = "playback")]
+impl Playback {
+    fn start(samples: &[f32], sample_rate: u32) -> Result<Self> {
+        let (stream, handle) = rodio::OutputStream::try_default()?;
+        let sink = rodio::Sink::try_new(&handle)?;
+        sink.append(
+            rodio::buffer::SamplesBuffer::new(1, sample_rate, samples.to_vec()).repeat_infinite(),
+        );
+        Ok(Self {
+            _stream: stream,
+            _sink: sink,
+        })
+    }
+}
+
+pub struct Audio {
+    /// Mixed down to mono.
+    samples: Vec<f32>,
+    sample_rate: u32,
+    /// `None` when there is no audio device, in which case the track keeps
+    /// running silently.
+    #[cfg(feature = "playback")]
+    _playback: Option<Playback>,
+    started: Instant,
+    /// Bin magnitudes, averaged over time like `AnalyserNode` does.
+    spectrum: Vec<f32>,
+    pub texture: texture::Texture,
+}
+
+impl Audio {
+    pub fn load<P: AsRef<Path>>(device: &wgpu::Device, path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut reader = hound::WavReader::open(path)?;
+        let spec = reader.spec();
+        let samples = match spec.sample_format {
+            hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<Vec<_>, _>>()?,
+            hound::SampleFormat::Int => {
+                let scale = 1.0 / (1u64 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .samples::<i32>()
+                    .map(|sample| sample.map(|sample| sample as f32 * scale))
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+        };
+        let samples = samples
+            .chunks(spec.channels as usize)
+            .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+            .collect::<Vec<_>>();
+        ensure!(!samples.is_empty(), "{} has no samples", path.display());
+
+        let texture = texture::Texture::create_data_texture(
+            device,
+            WIDTH as u32,
+            2,
+            wgpu::TextureFormat::R8Unorm,
+            &path.to_string_lossy(),
+        );
+
+        #[cfg(feature = "playback")]
+        let playback = Playback::start(&samples, spec.sample_rate)
+            .map_err(|e| log::warn!("Unable to play {}: {}", path.display(), e))
+            .ok();
+
+        Ok(Self {
+            samples,
+            sample_rate: spec.sample_rate,
+            #[cfg(feature = "playback")]
+            _playback: playback,
+            started: Instant::now(),
+            spectrum: vec![0.0; WIDTH],
+            texture,
+        })
+    }
+
+    /// Analyzes the samples that have just been played and uploads the
+    /// result.
+    pub fn update(&mut self, queue: &wgpu::Queue) {
+        let time = self.started.elapsed().as_secs_f64();
+        let end = (time * self.sample_rate as f64) as i64;
+        let len = self.samples.len() as i64;
+        let window = (0..FFT_SIZE)
+            .map(|i| self.samples[(end - FFT_SIZE as i64 + i as i64).rem_euclid(len) as usize])
+            .collect::<Vec<_>>();
+
+        let mut re = window
+            .iter()
+            .enumerate()
+            .map(|(i, sample)| sample * blackman(i, FFT_SIZE))
+            .collect::<Vec<_>>();
+        let mut im = vec![0.0; FFT_SIZE];
+        fft(&mut re, &mut im);
+
+        let mut data = vec![0u8; 2 * WIDTH];
+        for (bin, smoothed) in self.spectrum.iter_mut().enumerate() {
+            let magnitude = (re[bin] * re[bin] + im[bin] * im[bin]).sqrt() / FFT_SIZE as f32;
+            *smoothed = SMOOTHING * *smoothed + (1.0 - SMOOTHING) * magnitude;
+            let decibels = 20.0 * smoothed.max(f32::MIN_POSITIVE).log10();
+            let level = (decibels - MIN_DECIBELS) / (MAX_DECIBELS - MIN_DECIBELS);
+            data[bin] = (255.0 * level.clamp(0.0, 1.0)) as u8;
+        }
+        for (i, sample) in window[FFT_SIZE - WIDTH..].iter().enumerate() {
+            data[WIDTH + i] = (128.0 * (sample + 1.0)).clamp(0.0, 255.0) as u8;
+        }
+
+        queue.write_texture(
+            wgpu::TextureCopyView {
+                texture: &self.texture.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            &data,
+            wgpu::TextureDataLayout {
+                offset: 0,
+                bytes_per_row: WIDTH as u32,
+                rows_per_image: 2,
+            },
+            wgpu::Extent3d {
+                width: WIDTH as u32,
+                height: 2,
+                depth: 1,
+            },
+        );
+    }
+}
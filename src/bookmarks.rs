@@ -0,0 +1,266 @@
+//! Numbered viewpoints to come back to. Each scene keeps its bookmarks in a
+//! text file with one `slot x y z yaw pitch fovy` line per bookmark, angles
+//! in radians.
+
+use anyhow::*;
+use std::fs::{read_to_string, write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::angle::Rad;
+use crate::camera::{Camera, Projection};
+use crate::Vec3;
+
+const SLOTS: usize = 9;
+/// Seconds it takes to fly to a bookmark.
+const TRANSITION_TIME: f32 = 0.5;
+
+/// Where the bookmarks of the scene loaded from `scene` are kept, e.g.
+/// `cube.bookmarks` for `res/cube.obj`.
+pub fn file_for(scene: &Path) -> PathBuf {
+    PathBuf::from(scene.file_name().unwrap_or_default()).with_extension("bookmarks")
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Bookmark {
+    pub position: Vec3,
+    pub yaw: Rad,
+    pub pitch: Rad,
+    pub fovy: Rad,
+}
+
+impl Bookmark {
+    pub fn of(camera: &Camera, projection: &Projection) -> Self {
+        Self {
+            position: camera.position,
+            // Leaves out the whole turns mouse look piles up
+            yaw: camera.yaw.wrapped(),
+            pitch: camera.pitch,
+            fovy: projection.fovy,
+        }
+    }
+
+    fn apply(&self, camera: &mut Camera, projection: &mut Projection) {
+        camera.position = self.position;
+        camera.yaw = self.yaw;
+        camera.pitch = self.pitch;
+        projection.fovy = self.fovy;
+    }
+}
+
+/// Slots 1 to `SLOTS`, stored from index 0.
+#[derive(Debug, Default)]
+pub struct Bookmarks {
+    slots: [Option<Bookmark>; SLOTS],
+}
+
+impl Bookmarks {
+    /// A scene that has no bookmarks file yet has no bookmarks.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut bookmarks = Self::default();
+        if !path.exists() {
+            return Ok(bookmarks);
+        }
+
+        for (n, line) in read_to_string(path)?
+            .lines()
+            .enumerate()
+            .map(|(n, line)| (n + 1, line.split('#').next().unwrap_or_default().trim()))
+            .filter(|(_, line)| !line.is_empty())
+        {
+            let mut words = line.split_whitespace();
+            let slot = words
+                .next()
+                .unwrap_or_default()
+                .parse::<usize>()
+                .ok()
+                .filter(|slot| (1..=SLOTS).contains(slot))
+                .with_context(|| {
+                    format!("{}:{}: Slots go from 1 to {}", path.display(), n, SLOTS)
+                })?;
+            let values = words
+                .map(str::parse::<f32>)
+                .collect::<Result<Vec<_>, _>>()
+                .with_context(|| format!("{}:{}", path.display(), n))?;
+            bookmarks.slots[slot - 1] = match values[..] {
+                [x, y, z, yaw, pitch, fovy] => Some(Bookmark {
+                    position: Vec3::new(x, y, z),
+                    yaw: Rad(yaw),
+                    pitch: Rad(pitch),
+                    fovy: Rad(fovy),
+                }),
+                _ => bail!(
+                    "{}:{}: Expected `slot x y z yaw pitch fovy`",
+                    path.display(),
+                    n
+                ),
+            };
+        }
+        Ok(bookmarks)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut text = String::from("# slot x y z yaw pitch fovy\n");
+        for (index, bookmark) in self.slots.iter().enumerate() {
+            if let Some(mark) = bookmark {
+                text += &format!(
+                    "{} {} {} {} {} {} {}\n",
+                    index + 1,
+                    mark.position.x,
+                    mark.position.y,
+                    mark.position.z,
+                    mark.yaw.0,
+                    mark.pitch.0,
+                    mark.fovy.0
+                );
+            }
+        }
+        write(path, text)?;
+        Ok(())
+    }
+
+    pub fn get(&self, slot: usize) -> Option<Bookmark> {
+        self.slots[slot - 1]
+    }
+
+    pub fn set(&mut self, slot: usize, bookmark: Bookmark) {
+        self.slots[slot - 1] = Some(bookmark);
+    }
+}
+
+/// Flies the camera to a bookmark in place of the camera controller.
+#[derive(Debug)]
+pub struct Transition {
+    from: Bookmark,
+    to: Bookmark,
+    elapsed: f32,
+}
+
+impl Transition {
+    pub fn new(from: Bookmark, to: Bookmark) -> Self {
+        Self {
+            from,
+            to,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Returns false once the bookmark has been reached.
+    pub fn update(
+        &mut self,
+        camera: &mut Camera,
+        projection: &mut Projection,
+        dt: Duration,
+    ) -> bool {
+        self.elapsed += dt.as_secs_f32();
+        let t = (self.elapsed / TRANSITION_TIME).min(1.0);
+        // Eases in and out
+        let t = t * t * (3.0 - 2.0 * t);
+
+        let (from, to) = (self.from, self.to);
+        Bookmark {
+            position: from.position + (to.position - from.position) * t,
+            yaw: from.yaw.lerp(to.yaw, t),
+            pitch: from.pitch + (to.pitch - from.pitch) * t,
+            fovy: from.fovy + (to.fovy - from.fovy) * t,
+        }
+        .apply(camera, projection);
+        self.elapsed < TRANSITION_TIME
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bookmark(x: f32) -> Bookmark {
+        Bookmark {
+            position: Vec3::new(x, -x, 0.5 * x),
+            yaw: Rad(0.25 * x),
+            pitch: Rad(-0.1 * x),
+            fovy: Rad(0.8),
+        }
+    }
+
+    fn temp_file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "{}_{}_{}",
+            env!("CARGO_PKG_NAME"),
+            std::process::id(),
+            name
+        ))
+    }
+
+    #[test]
+    fn file_is_named_after_the_scene() {
+        assert_eq!(
+            file_for(Path::new("res/cube.obj")),
+            PathBuf::from("cube.bookmarks")
+        );
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let file = temp_file("round_trip.bookmarks");
+        let mut bookmarks = Bookmarks::default();
+        bookmarks.set(1, bookmark(1.0));
+        bookmarks.set(9, bookmark(-3.0));
+        bookmarks.save(&file).unwrap();
+        let loaded = Bookmarks::load(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        for slot in 1..=SLOTS {
+            match (loaded.get(slot), bookmarks.get(slot)) {
+                (Some(a), Some(b)) => {
+                    assert_eq!(a.position, b.position);
+                    assert_eq!((a.yaw, a.pitch, a.fovy), (b.yaw, b.pitch, b.fovy));
+                }
+                (None, None) => {}
+                (a, b) => panic!("Slot {}: {:?} is not {:?}", slot, a, b),
+            }
+        }
+    }
+
+    #[test]
+    fn missing_file_has_no_bookmarks() {
+        let bookmarks = Bookmarks::load(temp_file("missing.bookmarks")).unwrap();
+        assert!((1..=SLOTS).all(|slot| bookmarks.get(slot).is_none()));
+    }
+
+    #[test]
+    fn load_rejects_malformed_lines() {
+        for (name, text) in &[
+            ("slot_zero.bookmarks", "0 0 0 0 0 0 1\n"),
+            ("slot_ten.bookmarks", "10 0 0 0 0 0 1\n"),
+            ("no_slot.bookmarks", "one 0 0 0 0 0 1\n"),
+            ("too_few.bookmarks", "1 0 0 0 0 0\n"),
+            ("too_many.bookmarks", "1 0 0 0 0 0 1 2\n"),
+            ("not_a_number.bookmarks", "1 0 0 zero 0 0 1\n"),
+        ] {
+            let file = temp_file(name);
+            std::fs::write(&file, text).unwrap();
+            let result = Bookmarks::load(&file);
+            std::fs::remove_file(&file).unwrap();
+            assert!(result.is_err(), "{} loaded", name);
+        }
+    }
+
+    #[test]
+    fn transition_ends_on_the_bookmark() {
+        let (from, to) = (bookmark(0.0), bookmark(2.0));
+        let mut camera = Camera::new(Vec3::zero(), Rad(0.0), Rad(0.0));
+        let mut projection = Projection::new(16, 9, Rad(1.0), 0.1, 100.0);
+        let mut transition = Transition::new(from, to);
+        let step = Duration::from_secs_f32(TRANSITION_TIME / 4.0);
+        let mut steps = 0;
+        while transition.update(&mut camera, &mut projection, step) {
+            steps += 1;
+            assert!(steps < 10, "The transition never ends");
+        }
+        assert!((camera.position - to.position).mag() < 1e-5);
+        assert!((camera.yaw.0 - to.yaw.0).abs() < 1e-5);
+        assert!((camera.pitch.0 - to.pitch.0).abs() < 1e-5);
+        assert!((projection.fovy.0 - to.fovy.0).abs() < 1e-5);
+    }
+}
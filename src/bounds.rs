@@ -0,0 +1,308 @@
+//! Bounding volumes, and the tests that decide whether something can be seen
+//! at all before any of it gets drawn, or whether the cursor is over it.
+
+use crate::{Mat4, Vec2, Vec3, Vec4};
+
+/// Axis aligned bounding box.
+#[derive(Debug, Copy, Clone)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    /// Holds nothing and intersects nothing.
+    pub fn empty() -> Self {
+        Self {
+            min: Vec3::broadcast(f32::INFINITY),
+            max: Vec3::broadcast(f32::NEG_INFINITY),
+        }
+    }
+
+    /// The smallest box around `points`.
+    pub fn from_points<I: IntoIterator<Item = Vec3>>(points: I) -> Self {
+        points.into_iter().fold(Self::empty(), |aabb, point| Self {
+            min: aabb.min.min_by_component(point),
+            max: aabb.max.max_by_component(point),
+        })
+    }
+
+    /// The smallest box around both.
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            min: self.min.min_by_component(other.min),
+            max: self.max.max_by_component(other.max),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) / 2.0
+    }
+
+    /// Radius of the sphere around `center` that holds the box.
+    pub fn radius(&self) -> f32 {
+        (self.max - self.min).mag() / 2.0
+    }
+
+    pub fn corners(&self) -> [Vec3; 8] {
+        let (min, max) = (self.min, self.max);
+        [
+            Vec3::new(min.x, min.y, min.z),
+            Vec3::new(max.x, min.y, min.z),
+            Vec3::new(min.x, max.y, min.z),
+            Vec3::new(max.x, max.y, min.z),
+            Vec3::new(min.x, min.y, max.z),
+            Vec3::new(max.x, min.y, max.z),
+            Vec3::new(min.x, max.y, max.z),
+            Vec3::new(max.x, max.y, max.z),
+        ]
+    }
+
+    /// The box around this one once `transform` has been applied to it.
+    pub fn transformed(&self, transform: &Mat4) -> Self {
+        Self::from_points(
+            self.corners()
+                .iter()
+                .map(|corner| transform.transform_point3(*corner)),
+        )
+    }
+
+    /// Radius of the sphere around the origin that holds the box however it
+    /// is rotated.
+    pub fn radius_about_origin(&self) -> f32 {
+        self.corners()
+            .iter()
+            .map(|corner| corner.mag())
+            .fold(0.0, f32::max)
+    }
+}
+
+/// The planes bounding what a view-projection matrix can see.
+#[derive(Debug)]
+pub struct Frustum {
+    /// Each plane's normal points inwards, so a point `p` is inside when
+    /// `plane.xyz · p + plane.w >= 0` for every plane.
+    planes: Vec<Vec4>,
+}
+
+impl Frustum {
+    /// Extracts the planes from the rows of `view_proj`, clip space being
+    /// -w..w in x and y and 0..w in z. Which of the two z planes is the near
+    /// one doesn't matter, so reversed depth needs no special case. An
+    /// infinite far plane has no normal and is left out.
+    pub fn from_matrix(view_proj: &Mat4) -> Self {
+        let [x, y, z, w] = view_proj.transposed().cols;
+        let planes = [w + x, w - x, w + y, w - y, z, w - z]
+            .iter()
+            .filter_map(|plane| {
+                let length = plane.truncated().mag();
+                if length > f32::EPSILON {
+                    Some(*plane / length)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        Self { planes }
+    }
+
+    fn distance(plane: Vec4, point: Vec3) -> f32 {
+        plane.truncated().dot(point) + plane.w
+    }
+
+    /// Conservative: a box near a corner of the frustum may pass without
+    /// actually being visible.
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|&plane| {
+            // The corner furthest along the plane's normal
+            let normal = plane.truncated();
+            let pick = |n: f32, min: f32, max: f32| if n >= 0.0 { max } else { min };
+            let corner = Vec3::new(
+                pick(normal.x, aabb.min.x, aabb.max.x),
+                pick(normal.y, aabb.min.y, aabb.max.y),
+                pick(normal.z, aabb.min.z, aabb.max.z),
+            );
+            Self::distance(plane, corner) >= 0.0
+        })
+    }
+
+    pub fn intersects_sphere(&self, center: Vec3, radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|&plane| Self::distance(plane, center) >= -radius)
+    }
+}
+
+/// The points `origin + direction * t` for `t >= 0`.
+#[derive(Debug, Copy, Clone)]
+pub struct Ray {
+    pub origin: Vec3,
+    pub direction: Vec3,
+}
+
+impl Ray {
+    /// The ray from the near plane through `ndc`, a point in normalized
+    /// device coordinates. `near_depth` is the depth the near plane maps to.
+    pub fn unproject(view_proj: &Mat4, ndc: Vec2, near_depth: f32) -> Self {
+        let inverse = view_proj.inversed();
+        let unproject = |depth: f32| {
+            let point = inverse * Vec4::new(ndc.x, ndc.y, depth, 1.0);
+            point.truncated() / point.w
+        };
+        // Halfway through the depth range is still a finite distance away
+        // when the far plane is at infinity
+        let origin = unproject(near_depth);
+        Self {
+            origin,
+            direction: (unproject(0.5) - origin).normalized(),
+        }
+    }
+
+    /// The direction isn't renormalized, so `t` measures the same distance
+    /// along the transformed ray as along this one.
+    pub fn transformed(&self, transform: &Mat4) -> Self {
+        Self {
+            origin: transform.transform_point3(self.origin),
+            direction: transform.transform_vec3(self.direction),
+        }
+    }
+
+    /// The `t` at which the ray enters the box, or 0 when it starts inside.
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let inverse = Vec3::one() / self.direction;
+        let t0 = (aabb.min - self.origin) * inverse;
+        let t1 = (aabb.max - self.origin) * inverse;
+        let enter = t0.min_by_component(t1).component_max().max(0.0);
+        let exit = t0.max_by_component(t1).component_min();
+        if enter <= exit {
+            Some(enter)
+        } else {
+            None
+        }
+    }
+
+    /// Möller-Trumbore. Triangles are hit from either side.
+    pub fn intersect_triangle(&self, [a, b, c]: [Vec3; 3]) -> Option<f32> {
+        let edge1 = b - a;
+        let edge2 = c - a;
+        let p = self.direction.cross(edge2);
+        let det = edge1.dot(p);
+        if det.abs() < f32::EPSILON {
+            return None;
+        }
+
+        let s = self.origin - a;
+        let u = s.dot(p) / det;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = s.cross(edge1);
+        let v = self.direction.dot(q) / det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = edge2.dot(q) / det;
+        if t >= 0.0 {
+            Some(t)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Looks down -z from z = 10 with a 90° field of view.
+    fn perspective() -> Mat4 {
+        let view = Mat4::look_at(Vec3::new(0.0, 0.0, 10.0), Vec3::zero(), Vec3::unit_y());
+        ultraviolet::projection::perspective_wgpu_dx(std::f32::consts::FRAC_PI_2, 1.0, 0.1, 100.0)
+            * view
+    }
+
+    fn reversed_infinite() -> Mat4 {
+        let view = Mat4::look_at(Vec3::new(0.0, 0.0, 10.0), Vec3::zero(), Vec3::unit_y());
+        ultraviolet::projection::perspective_reversed_infinite_z_wgpu_dx_gl(
+            std::f32::consts::FRAC_PI_2,
+            1.0,
+            0.1,
+        ) * view
+    }
+
+    fn cube(center: Vec3) -> Aabb {
+        Aabb {
+            min: center - Vec3::one(),
+            max: center + Vec3::one(),
+        }
+    }
+
+    #[test]
+    fn planes_are_normalized_and_face_inwards() {
+        let frustum = Frustum::from_matrix(&perspective());
+        assert_eq!(frustum.planes.len(), 6);
+        for &plane in &frustum.planes {
+            assert!((plane.truncated().mag() - 1.0).abs() < 1e-5);
+            // The point the camera looks at is inside every plane
+            assert!(Frustum::distance(plane, Vec3::zero()) > 0.0);
+        }
+    }
+
+    #[test]
+    fn infinite_far_plane_is_left_out() {
+        let frustum = Frustum::from_matrix(&reversed_infinite());
+        assert_eq!(frustum.planes.len(), 5);
+        assert!(frustum.intersects_aabb(&cube(Vec3::new(0.0, 0.0, -10_000.0))));
+    }
+
+    #[test]
+    fn aabb_inside_outside_and_straddling() {
+        for view_proj in &[perspective(), reversed_infinite()] {
+            let frustum = Frustum::from_matrix(view_proj);
+            assert!(frustum.intersects_aabb(&cube(Vec3::zero())));
+            // Behind the camera
+            assert!(!frustum.intersects_aabb(&cube(Vec3::new(0.0, 0.0, 20.0))));
+            // Off to the side, where 90° can't see from 10 units away
+            assert!(!frustum.intersects_aabb(&cube(Vec3::new(15.0, 0.0, 0.0))));
+            assert!(!frustum.intersects_aabb(&cube(Vec3::new(0.0, -15.0, 0.0))));
+            // Only a corner pokes in past the left plane
+            assert!(frustum.intersects_aabb(&cube(Vec3::new(-10.5, 0.0, 0.0))));
+        }
+    }
+
+    #[test]
+    fn aabb_past_the_far_plane() {
+        let frustum = Frustum::from_matrix(&perspective());
+        assert!(!frustum.intersects_aabb(&cube(Vec3::new(0.0, 0.0, -200.0))));
+        assert!(frustum.intersects_aabb(&cube(Vec3::new(0.0, 0.0, -90.5))));
+    }
+
+    #[test]
+    fn sphere_inside_outside_and_touching() {
+        let frustum = Frustum::from_matrix(&perspective());
+        assert!(frustum.intersects_sphere(Vec3::zero(), 1.0));
+        assert!(!frustum.intersects_sphere(Vec3::new(0.0, 0.0, 20.0), 1.0));
+        // The right plane runs through (10, 0, 0) at 45°, so the center is
+        // 2 / sqrt(2) units outside it
+        let center = Vec3::new(12.0, 0.0, 0.0);
+        assert!(!frustum.intersects_sphere(center, 1.0));
+        assert!(frustum.intersects_sphere(center, 1.5));
+    }
+
+    #[test]
+    fn orthographic() {
+        let view = Mat4::look_at(Vec3::new(0.0, 0.0, 10.0), Vec3::zero(), Vec3::unit_y());
+        let projection =
+            ultraviolet::projection::orthographic_wgpu_dx(-5.0, 5.0, -5.0, 5.0, 0.1, 100.0);
+        let frustum = Frustum::from_matrix(&(projection * view));
+        assert_eq!(frustum.planes.len(), 6);
+        assert!(frustum.intersects_aabb(&cube(Vec3::new(5.5, 0.0, 0.0))));
+        assert!(!frustum.intersects_aabb(&cube(Vec3::new(6.5, 0.0, 0.0))));
+        assert!(frustum.intersects_sphere(Vec3::new(0.0, 5.5, 0.0), 1.0));
+        assert!(!frustum.intersects_sphere(Vec3::new(0.0, 6.5, 0.0), 1.0));
+    }
+}
@@ -1,13 +1,14 @@
-use crate::angle::Rad;
-use crate::{Mat4, Vec3};
+use crate::angle::{Deg, Rad};
+use crate::bounds::Aabb;
+use crate::{Mat4, Rotor3, Vec3};
 use std::{f32::consts::FRAC_PI_2, time::Duration};
 use winit::{dpi::PhysicalPosition, event::*};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Camera {
     pub position: Vec3,
-    yaw: Rad,
-    pitch: Rad,
+    pub yaw: Rad,
+    pub pitch: Rad,
 }
 
 impl Camera {
@@ -19,20 +20,143 @@ impl Camera {
         }
     }
 
+    /// The direction the camera looks in.
+    pub fn forward(&self) -> Vec3 {
+        let (yaw_sin, yaw_cos) = self.yaw.sin_cos();
+        let (pitch_sin, pitch_cos) = self.pitch.sin_cos();
+        Vec3::new(pitch_cos * yaw_cos, pitch_sin, pitch_cos * yaw_sin).normalized()
+    }
+
     pub fn calc_matrix(&self) -> Mat4 {
         Mat4::look_at(
             self.position,
-            Vec3::new(self.yaw.0.cos(), self.pitch.0.sin(), self.yaw.0.sin()).normalized(),
+            self.position + self.forward(),
             Vec3::unit_y(),
         )
     }
 }
 
+/// Anything the scene can be viewed through.
+pub trait View {
+    fn position(&self) -> Vec3;
+    fn calc_matrix(&self) -> Mat4;
+}
+
+impl View for Camera {
+    fn position(&self) -> Vec3 {
+        self.position
+    }
+
+    fn calc_matrix(&self) -> Mat4 {
+        Camera::calc_matrix(self)
+    }
+}
+
+/// A camera that can face any way and roll, for scenes with no particular
+/// up. Rather than angles it keeps a rotation from view space, where it
+/// looks down -z with y up, so no orientation locks it the way a yaw and
+/// pitch camera locks when looking straight up or down.
+#[derive(Debug, Clone)]
+pub struct FreeCamera {
+    pub position: Vec3,
+    pub orientation: Rotor3,
+}
+
+impl FreeCamera {
+    /// Looks the way `camera` does, level with the horizon.
+    pub fn from_camera(camera: &Camera) -> Self {
+        let heading = Rotor3::from_rotation_xz((camera.yaw + Deg(90.0)).into());
+        Self {
+            position: camera.position,
+            orientation: heading * Rotor3::from_rotation_yz(camera.pitch.into()),
+        }
+    }
+
+    /// The yaw and pitch camera looking the same way, which can't roll.
+    pub fn to_camera(&self) -> Camera {
+        let forward = self.forward();
+        Camera::new(
+            self.position,
+            Rad(forward.z.atan2(forward.x)),
+            Rad(forward.y.clamp(-1.0, 1.0).asin()),
+        )
+    }
+
+    pub fn forward(&self) -> Vec3 {
+        self.orientation * -Vec3::unit_z()
+    }
+
+    pub fn right(&self) -> Vec3 {
+        self.orientation * Vec3::unit_x()
+    }
+
+    pub fn up(&self) -> Vec3 {
+        self.orientation * Vec3::unit_y()
+    }
+}
+
+impl View for FreeCamera {
+    fn position(&self) -> Vec3 {
+        self.position
+    }
+
+    fn calc_matrix(&self) -> Mat4 {
+        Mat4::look_at(self.position, self.position + self.forward(), self.up())
+    }
+}
+
+/// How depth is laid out in the depth buffer. The projection, the depth
+/// test and the value the depth buffer is cleared to all have to agree.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DepthMode {
+    /// 0 at the near plane and 1 at the far plane.
+    Standard,
+    /// 1 at the near plane, falling towards 0 at infinity. Floats keep
+    /// their precision close to 0, which evens it out over the distance,
+    /// and there is no far plane to clip the scene.
+    ReverseZ,
+}
+
+impl DepthMode {
+    pub fn compare(self) -> wgpu::CompareFunction {
+        match self {
+            DepthMode::Standard => wgpu::CompareFunction::Less,
+            DepthMode::ReverseZ => wgpu::CompareFunction::Greater,
+        }
+    }
+
+    /// The depth of the farthest possible point.
+    pub fn clear_value(self) -> f32 {
+        match self {
+            DepthMode::Standard => 1.0,
+            DepthMode::ReverseZ => 0.0,
+        }
+    }
+
+    /// The depth of a point on the near plane.
+    pub fn near_value(self) -> f32 {
+        1.0 - self.clear_value()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ProjectionMode {
+    Perspective,
+    /// Shows `height` world units vertically, however far away they are.
+    Orthographic {
+        height: f32,
+    },
+}
+
+#[derive(Debug, Clone)]
 pub struct Projection {
     aspect: f32,
-    fovy: Rad,
+    pub fovy: Rad,
     znear: f32,
     zfar: f32,
+    pub mode: ProjectionMode,
+    /// With `ReverseZ`, `zfar` only bounds orthographic projections.
+    pub depth_mode: DepthMode,
 }
 
 impl Projection {
@@ -42,6 +166,8 @@ impl Projection {
             fovy: fovy.into(),
             znear,
             zfar,
+            mode: ProjectionMode::Perspective,
+            depth_mode: DepthMode::Standard,
         }
     }
 
@@ -49,32 +175,182 @@ impl Projection {
         self.aspect = width as f32 / height as f32;
     }
 
+    /// Switches between perspective and orthographic, keeping the size on
+    /// screen of whatever is `focus_distance` in front of the camera.
+    pub fn toggle_orthographic(&mut self, focus_distance: f32) {
+        self.mode = match self.mode {
+            ProjectionMode::Perspective => ProjectionMode::Orthographic {
+                height: 2.0 * focus_distance * (self.fovy / 2.0).tan(),
+            },
+            ProjectionMode::Orthographic { .. } => ProjectionMode::Perspective,
+        };
+    }
+
+    /// Moves `camera` back along its view direction until `bounds` fill the
+    /// view, and fits the clip planes around them.
+    pub fn frame(&mut self, camera: &mut Camera, bounds: &Aabb) {
+        let center = bounds.center();
+        let radius = bounds.radius().max(f32::EPSILON);
+        let distance = match self.mode {
+            ProjectionMode::Perspective => {
+                // The sphere around the bounds touches the narrower side
+                let half_fovy = self.fovy / 2.0;
+                let half_fovx = Rad((half_fovy.tan() * self.aspect).atan());
+                radius / half_fovy.sin().min(half_fovx.sin())
+            }
+            ProjectionMode::Orthographic { .. } => {
+                self.mode = ProjectionMode::Orthographic {
+                    height: 2.0 * radius * (1.0 / self.aspect).max(1.0),
+                };
+                2.0 * radius
+            }
+        };
+        camera.position = center - camera.forward() * distance;
+
+        // Some room to move in and out before anything gets clipped
+        self.znear = ((distance - radius) / 2.0).max(radius / 1000.0);
+        self.zfar = 2.0 * (distance + radius);
+    }
+
     pub fn calc_matrix(&self) -> Mat4 {
-        ultraviolet::projection::perspective_wgpu_dx(
-            self.fovy.into(),
-            self.aspect,
-            self.znear,
-            self.zfar,
-        )
+        match (self.mode, self.depth_mode) {
+            (ProjectionMode::Perspective, DepthMode::Standard) => {
+                ultraviolet::projection::perspective_wgpu_dx(
+                    self.fovy.into(),
+                    self.aspect,
+                    self.znear,
+                    self.zfar,
+                )
+            }
+            (ProjectionMode::Perspective, DepthMode::ReverseZ) => {
+                ultraviolet::projection::perspective_reversed_infinite_z_wgpu_dx_gl(
+                    self.fovy.into(),
+                    self.aspect,
+                    self.znear,
+                )
+            }
+            (ProjectionMode::Orthographic { height }, depth_mode) => {
+                let top = height / 2.0;
+                let right = top * self.aspect;
+                // Swapping the planes maps the near one to 1 and the far one to 0
+                let (near, far) = match depth_mode {
+                    DepthMode::Standard => (self.znear, self.zfar),
+                    DepthMode::ReverseZ => (self.zfar, self.znear),
+                };
+                ultraviolet::projection::orthographic_wgpu_dx(-right, right, -top, top, near, far)
+            }
+        }
     }
 }
 
+/// Turns input into camera movement. `State` forwards window events to the
+/// active viewport's controller and lets it move the camera once per frame,
+/// so any input scheme, scripted ones included, can drive the camera.
+pub trait CameraInput {
+    /// Returns whether the key was used.
+    fn process_keyboard(&mut self, key: VirtualKeyCode, state: ElementState) -> bool;
+    /// Mouse movement while the left button is held.
+    fn process_mouse(&mut self, mouse_dx: f64, mouse_dy: f64);
+    /// Mouse movement while the middle button is held.
+    fn process_pan(&mut self, _mouse_dx: f64, _mouse_dy: f64) {}
+    fn process_scroll(&mut self, delta: &MouseScrollDelta);
+}
+
+/// A `CameraInput` that moves cameras of type `C`.
+pub trait CameraControl<C: View = Camera>: CameraInput {
+    fn update_camera(&mut self, camera: &mut C, dt: Duration);
+    /// Called when the controller starts driving `camera` after another one.
+    fn take_over(&mut self, _camera: &C) {}
+    /// Called when `camera` has been pointed at `target` for the controller.
+    fn focus(&mut self, _camera: &C, _target: Vec3) {}
+    /// How far ahead of the camera the point the controller moves around
+    /// is, for controllers that have one.
+    fn focus_distance(&self) -> Option<f32> {
+        None
+    }
+}
+
+impl<C: View> CameraInput for Box<dyn CameraControl<C>> {
+    fn process_keyboard(&mut self, key: VirtualKeyCode, state: ElementState) -> bool {
+        (**self).process_keyboard(key, state)
+    }
+
+    fn process_mouse(&mut self, mouse_dx: f64, mouse_dy: f64) {
+        (**self).process_mouse(mouse_dx, mouse_dy)
+    }
+
+    fn process_pan(&mut self, mouse_dx: f64, mouse_dy: f64) {
+        (**self).process_pan(mouse_dx, mouse_dy)
+    }
+
+    fn process_scroll(&mut self, delta: &MouseScrollDelta) {
+        (**self).process_scroll(delta)
+    }
+}
+
+/// Fraction of the way to its target that something easing towards it with
+/// the given `sharpness` covers in `dt` seconds. Easing this way gives the
+/// same motion no matter how the time is split into frames. An infinite
+/// sharpness snaps straight to the target.
+fn ease(sharpness: f32, dt: f32) -> f32 {
+    if sharpness.is_infinite() {
+        return 1.0;
+    }
+    1.0 - (-sharpness * dt).exp()
+}
+
+/// Eases `velocity` towards `target` with the given `sharpness` over `dt`
+/// seconds, and returns how far that moves. Integrating the eased velocity
+/// exactly keeps the distance the same however the time is split.
+fn coast(velocity: &mut Vec3, target: Vec3, sharpness: f32, dt: f32) -> Vec3 {
+    let eased = ease(sharpness, dt);
+    let coasting = if sharpness > 0.0 {
+        eased / sharpness
+    } else {
+        dt
+    };
+    let distance = target * dt + (*velocity - target) * coasting;
+    *velocity += (target - *velocity) * eased;
+    distance
+}
+
+/// The keys, mouse look and momentum of a first person flying camera,
+/// whichever way it keeps its orientation. Movement speeds up and slows
+/// down smoothly rather than starting and stopping dead, and mouse look
+/// trails the mouse slightly. Every rate can be set to `f32::INFINITY` to
+/// turn it off.
 #[derive(Debug)]
-pub struct CameraController {
+pub struct Flight {
     amount_left: f32,
     amount_right: f32,
     amount_forward: f32,
     amount_backward: f32,
     amount_up: f32,
     amount_down: f32,
+    sprinting: bool,
+    /// Mouse look and scrolling that has yet to be applied, in radians and
+    /// world units.
     rotate_horizontal: f32,
     rotate_vertical: f32,
     scroll: f32,
-    speed: f32,
-    sensitivity: f32,
+    velocity: Vec3,
+    /// World units per second.
+    pub speed: f32,
+    /// Radians per pixel the mouse moves. Scrolling moves `speed` times this
+    /// many units per pixel.
+    pub sensitivity: f32,
+    /// How quickly the camera gets up to speed while keys are held.
+    pub acceleration: f32,
+    /// How quickly the camera coasts to a stop once the keys are released.
+    pub damping: f32,
+    /// How quickly mouse look and scrolling catch up with the mouse.
+    pub rotation_damping: f32,
+    /// Speed multiplier while Left Alt is held. Control is left free for
+    /// shortcuts like saving bookmarks.
+    pub sprint_multiplier: f32,
 }
 
-impl CameraController {
+impl Flight {
     pub fn new(speed: f32, sensitivity: f32) -> Self {
         Self {
             amount_left: 0.0,
@@ -83,15 +359,76 @@ impl CameraController {
             amount_backward: 0.0,
             amount_up: 0.0,
             amount_down: 0.0,
+            sprinting: false,
             rotate_horizontal: 0.0,
             rotate_vertical: 0.0,
             scroll: 0.0,
+            velocity: Vec3::zero(),
             speed,
             sensitivity,
+            acceleration: 8.0,
+            damping: 6.0,
+            rotation_damping: 25.0,
+            sprint_multiplier: 3.0,
         }
     }
 
-    pub fn process_keyboard(&mut self, key: VirtualKeyCode, state: ElementState) -> bool {
+    /// Drops any leftover momentum.
+    fn stop(&mut self) {
+        self.velocity = Vec3::zero();
+        self.rotate_horizontal = 0.0;
+        self.rotate_vertical = 0.0;
+        self.scroll = 0.0;
+    }
+
+    /// Moves `position` for `dt` seconds, with `forward`, `right` and `up`
+    /// being the directions the movement keys move it in and `look` the
+    /// direction scrolling moves it in. Returns how far to turn the camera
+    /// right and up this frame, in radians.
+    fn fly(
+        &mut self,
+        position: &mut Vec3,
+        forward: Vec3,
+        right: Vec3,
+        up: Vec3,
+        look: Vec3,
+        dt: f32,
+    ) -> (f32, f32) {
+        let mut direction = forward * (self.amount_forward - self.amount_backward)
+            + right * (self.amount_right - self.amount_left)
+            + up * (self.amount_up - self.amount_down);
+        // Moving diagonally shouldn't be faster
+        if direction.mag_sq() > 1.0 {
+            direction.normalize();
+        }
+        let speed = if self.sprinting {
+            self.speed * self.sprint_multiplier
+        } else {
+            self.speed
+        };
+        let sharpness = if direction == Vec3::zero() {
+            self.damping
+        } else {
+            self.acceleration
+        };
+        *position += coast(&mut self.velocity, direction * speed, sharpness, dt);
+
+        let eased = ease(self.rotation_damping, dt);
+        *position += look * self.scroll * eased;
+        let turn = (
+            self.rotate_horizontal * eased,
+            -self.rotate_vertical * eased,
+        );
+
+        self.scroll -= self.scroll * eased;
+        self.rotate_horizontal -= self.rotate_horizontal * eased;
+        self.rotate_vertical -= self.rotate_vertical * eased;
+        turn
+    }
+}
+
+impl CameraInput for Flight {
+    fn process_keyboard(&mut self, key: VirtualKeyCode, state: ElementState) -> bool {
         let amount = if state == ElementState::Pressed {
             1.0
         } else {
@@ -122,50 +459,257 @@ impl CameraController {
                 self.amount_down = amount;
                 true
             }
+            VirtualKeyCode::LAlt => {
+                self.sprinting = state == ElementState::Pressed;
+                true
+            }
             _ => false,
         }
     }
 
-    pub fn process_mouse(&mut self, mouse_dx: f64, mouse_dy: f64) {
-        self.rotate_horizontal = mouse_dx as f32;
-        self.rotate_vertical = mouse_dy as f32;
+    fn process_mouse(&mut self, mouse_dx: f64, mouse_dy: f64) {
+        self.rotate_horizontal += mouse_dx as f32 * self.sensitivity;
+        self.rotate_vertical += mouse_dy as f32 * self.sensitivity;
     }
 
-    pub fn process_scroll(&mut self, delta: &MouseScrollDelta) {
-        self.scroll = -match delta {
+    fn process_scroll(&mut self, delta: &MouseScrollDelta) {
+        let pixels = match delta {
             // I'm assuming a line is about 100 pixels
             MouseScrollDelta::LineDelta(_, scroll) => scroll * 100.0,
             MouseScrollDelta::PixelDelta(PhysicalPosition { y: scroll, .. }) => *scroll as f32,
         };
+        self.scroll -= pixels * self.speed * self.sensitivity;
     }
+}
 
-    pub fn update_camera(&mut self, camera: &mut Camera, dt: Duration) {
-        let dt = dt.as_secs_f32();
+/// Keeps yaw and pitch cameras from looking straight up or down, where
+/// forward lines up with the world up that `look_at` needs and the view
+/// flips.
+const MAX_PITCH: Rad = Rad(FRAC_PI_2 - 0.01);
+
+/// First person flying camera that keeps its horizon level.
+#[derive(Debug)]
+pub struct CameraController {
+    pub flight: Flight,
+}
+
+impl CameraController {
+    pub fn new(speed: f32, sensitivity: f32) -> Self {
+        Self {
+            flight: Flight::new(speed, sensitivity),
+        }
+    }
+}
+
+impl CameraInput for CameraController {
+    fn process_keyboard(&mut self, key: VirtualKeyCode, state: ElementState) -> bool {
+        self.flight.process_keyboard(key, state)
+    }
+
+    fn process_mouse(&mut self, mouse_dx: f64, mouse_dy: f64) {
+        self.flight.process_mouse(mouse_dx, mouse_dy);
+    }
+
+    fn process_scroll(&mut self, delta: &MouseScrollDelta) {
+        self.flight.process_scroll(delta);
+    }
+}
+
+impl CameraControl for CameraController {
+    /// Drops any leftover momentum from the last time this controller was
+    /// active.
+    fn take_over(&mut self, _camera: &Camera) {
+        self.flight.stop();
+    }
 
-        let (yaw_sin, yaw_cos) = camera.yaw.0.sin_cos();
+    fn update_camera(&mut self, camera: &mut Camera, dt: Duration) {
+        let (yaw_sin, yaw_cos) = camera.yaw.sin_cos();
         let forward = Vec3::new(yaw_cos, 0.0, yaw_sin).normalized();
         let right = Vec3::new(-yaw_sin, 0.0, yaw_cos).normalized();
-        camera.position += forward * (self.amount_forward - self.amount_backward) * self.speed * dt;
-        camera.position += right * (self.amount_right - self.amount_left) * self.speed * dt;
+        let look = camera.forward();
+        let (yaw, pitch) = self.flight.fly(
+            &mut camera.position,
+            forward,
+            right,
+            Vec3::unit_y(),
+            look,
+            dt.as_secs_f32(),
+        );
+        camera.yaw += Rad(yaw);
+        camera.pitch += Rad(pitch);
+        camera.pitch = camera.pitch.clamp(-MAX_PITCH, MAX_PITCH);
+    }
+}
+
+const MIN_ORBIT_DISTANCE: f32 = 0.1;
+
+/// Circles the camera around a target point, for inspecting a single model.
+/// Dragging rotates around the target, the scroll wheel zooms and dragging
+/// with the middle button pans.
+#[derive(Debug)]
+pub struct OrbitController {
+    pub target: Vec3,
+    pub distance: f32,
+    rotate_horizontal: f32,
+    rotate_vertical: f32,
+    pan_horizontal: f32,
+    pan_vertical: f32,
+    scroll: f32,
+    /// Radians per pixel dragged.
+    sensitivity: f32,
+}
 
-        let (pitch_sin, pitch_cos) = camera.pitch.0.sin_cos();
-        let scrollward =
-            Vec3::new(pitch_cos * yaw_cos, pitch_sin, pitch_cos * yaw_sin).normalized();
-        camera.position += scrollward * self.scroll * self.speed * self.sensitivity * dt;
-        self.scroll = 0.;
+impl OrbitController {
+    pub fn new<V: Into<Vec3>>(target: V, distance: f32, sensitivity: f32) -> Self {
+        Self {
+            target: target.into(),
+            distance,
+            rotate_horizontal: 0.0,
+            rotate_vertical: 0.0,
+            pan_horizontal: 0.0,
+            pan_vertical: 0.0,
+            scroll: 0.0,
+            sensitivity,
+        }
+    }
+}
+
+impl CameraInput for OrbitController {
+    fn process_keyboard(&mut self, _key: VirtualKeyCode, _state: ElementState) -> bool {
+        false
+    }
 
-        camera.position.y += (self.amount_up - self.amount_down) * self.speed * dt;
+    fn process_mouse(&mut self, mouse_dx: f64, mouse_dy: f64) {
+        self.rotate_horizontal += mouse_dx as f32;
+        self.rotate_vertical += mouse_dy as f32;
+    }
 
-        camera.yaw += Rad(self.rotate_horizontal) * self.sensitivity * dt;
-        camera.pitch += Rad(-self.rotate_vertical) * self.sensitivity * dt;
+    fn process_pan(&mut self, mouse_dx: f64, mouse_dy: f64) {
+        self.pan_horizontal += mouse_dx as f32;
+        self.pan_vertical += mouse_dy as f32;
+    }
+
+    fn process_scroll(&mut self, delta: &MouseScrollDelta) {
+        self.scroll += -match delta {
+            // I'm assuming a line is about 100 pixels
+            MouseScrollDelta::LineDelta(_, scroll) => scroll * 100.0,
+            MouseScrollDelta::PixelDelta(PhysicalPosition { y: scroll, .. }) => *scroll as f32,
+        };
+    }
+}
+
+impl CameraControl for OrbitController {
+    /// Moves the target to where `camera` is looking, `distance` ahead, so
+    /// that the view stays where it was.
+    fn take_over(&mut self, camera: &Camera) {
+        self.target = camera.position + camera.forward() * self.distance;
+    }
+
+    fn focus(&mut self, camera: &Camera, target: Vec3) {
+        self.target = target;
+        self.distance = (camera.position - target).mag().max(MIN_ORBIT_DISTANCE);
+    }
+
+    fn focus_distance(&self) -> Option<f32> {
+        Some(self.distance)
+    }
+
+    fn update_camera(&mut self, camera: &mut Camera, _dt: Duration) {
+        camera.yaw += Rad(self.rotate_horizontal * self.sensitivity);
+        camera.pitch -= Rad(self.rotate_vertical * self.sensitivity);
+        camera.pitch = camera.pitch.clamp(-MAX_PITCH, MAX_PITCH);
+
+        // Panning and zooming scale with the distance, so they feel the same
+        // close up and far away
+        let forward = camera.forward();
+        let right = forward.cross(Vec3::unit_y()).normalized();
+        let up = right.cross(forward);
+        let pan_speed = self.distance * self.sensitivity * 0.2;
+        self.target += (up * self.pan_vertical - right * self.pan_horizontal) * pan_speed;
+        self.distance *= (self.scroll * self.sensitivity * 0.2).exp();
+        self.distance = self.distance.max(MIN_ORBIT_DISTANCE);
+
+        camera.position = self.target - forward * self.distance;
 
         self.rotate_horizontal = 0.0;
         self.rotate_vertical = 0.0;
+        self.pan_horizontal = 0.0;
+        self.pan_vertical = 0.0;
+        self.scroll = 0.0;
+    }
+}
 
-        if camera.pitch < -Rad(FRAC_PI_2) {
-            camera.pitch = -Rad(FRAC_PI_2);
-        } else if camera.pitch > Rad(FRAC_PI_2) {
-            camera.pitch = Rad(FRAC_PI_2);
+/// Flies a `FreeCamera` along its own axes. Dragging turns it about its own
+/// up and right axes and Q and E roll it, so the controls behave the same
+/// whichever way up it is. Moves and eases like `CameraController`.
+#[derive(Debug)]
+pub struct FreeFlyController {
+    pub flight: Flight,
+    amount_roll_left: f32,
+    amount_roll_right: f32,
+    /// Radians per second while Q or E is held.
+    pub roll_speed: f32,
+}
+
+impl FreeFlyController {
+    pub fn new(speed: f32, sensitivity: f32) -> Self {
+        Self {
+            flight: Flight::new(speed, sensitivity),
+            amount_roll_left: 0.0,
+            amount_roll_right: 0.0,
+            roll_speed: FRAC_PI_2,
         }
     }
 }
+
+impl CameraInput for FreeFlyController {
+    fn process_keyboard(&mut self, key: VirtualKeyCode, state: ElementState) -> bool {
+        let amount = if state == ElementState::Pressed {
+            1.0
+        } else {
+            0.0
+        };
+        match key {
+            VirtualKeyCode::Q => {
+                self.amount_roll_left = amount;
+                true
+            }
+            VirtualKeyCode::E => {
+                self.amount_roll_right = amount;
+                true
+            }
+            _ => self.flight.process_keyboard(key, state),
+        }
+    }
+
+    fn process_mouse(&mut self, mouse_dx: f64, mouse_dy: f64) {
+        self.flight.process_mouse(mouse_dx, mouse_dy);
+    }
+
+    fn process_scroll(&mut self, delta: &MouseScrollDelta) {
+        self.flight.process_scroll(delta);
+    }
+}
+
+impl CameraControl<FreeCamera> for FreeFlyController {
+    /// Drops any leftover momentum from the last camera it flew.
+    fn take_over(&mut self, _camera: &FreeCamera) {
+        self.flight.stop();
+    }
+
+    fn update_camera(&mut self, camera: &mut FreeCamera, dt: Duration) {
+        let dt = dt.as_secs_f32();
+        let (forward, right, up) = (camera.forward(), camera.right(), camera.up());
+        let (yaw, pitch) = self
+            .flight
+            .fly(&mut camera.position, forward, right, up, forward, dt);
+        let roll = (self.amount_roll_left - self.amount_roll_right) * self.roll_speed * dt;
+        // Each turn is about the camera's current axes, not the world's
+        camera.orientation = camera.orientation
+            * Rotor3::from_rotation_xz(yaw)
+            * Rotor3::from_rotation_yz(pitch)
+            * Rotor3::from_rotation_xy(roll);
+        // Stops rounding errors from building up over many small turns
+        camera.orientation.normalize();
+    }
+}
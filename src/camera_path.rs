@@ -0,0 +1,302 @@
+//! Recorded camera flythroughs. A path is a list of timed keyframes, saved as
+//! text with one `time x y z yaw pitch` line per keyframe, angles in radians.
+//! Playback runs a Catmull-Rom spline through the keyframes.
+
+use anyhow::*;
+use std::fs::{read_to_string, write};
+use std::ops::{Add, Mul, Sub};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::angle::Rad;
+use crate::camera::Camera;
+use crate::Vec3;
+
+/// Seconds between the keyframes taken while recording.
+const RECORD_INTERVAL: f32 = 0.2;
+
+#[derive(Debug, Copy, Clone)]
+pub struct Keyframe {
+    pub time: f32,
+    pub position: Vec3,
+    pub yaw: Rad,
+    pub pitch: Rad,
+}
+
+impl Keyframe {
+    fn of(camera: &Camera, time: f32) -> Self {
+        Self {
+            time,
+            position: camera.position,
+            yaw: camera.yaw,
+            pitch: camera.pitch,
+        }
+    }
+}
+
+/// The point `t` of the way from `p1` to `p2`.
+fn catmull_rom<T>(p0: T, p1: T, p2: T, p3: T, t: f32) -> T
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + Mul<f32, Output = T>,
+{
+    (p1 * 2.0
+        + (p2 - p0) * t
+        + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * (t * t)
+        + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * (t * t * t))
+        * 0.5
+}
+
+/// Like `catmull_rom`, but each angle first takes the shortest way round from
+/// `p1`, so a path crossing from 179° to -179° turns 2° and not 358°.
+fn catmull_rom_angle(p0: Rad, p1: Rad, p2: Rad, p3: Rad, t: f32) -> Rad {
+    let p2 = p1 + p1.shortest_arc_to(p2);
+    let p0 = p1 + p1.shortest_arc_to(p0);
+    let p3 = p2 + p2.shortest_arc_to(p3);
+    catmull_rom(p0, p1, p2, p3, t)
+}
+
+#[derive(Debug, Default)]
+pub struct CameraPath {
+    keyframes: Vec<Keyframe>,
+}
+
+impl CameraPath {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let keyframes = read_to_string(path)?
+            .lines()
+            .enumerate()
+            .map(|(n, line)| (n + 1, line.split('#').next().unwrap_or_default().trim()))
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(n, line)| {
+                let values = line
+                    .split_whitespace()
+                    .map(str::parse::<f32>)
+                    .collect::<Result<Vec<_>, _>>()
+                    .with_context(|| format!("{}:{}", path.display(), n))?;
+                match values[..] {
+                    [time, x, y, z, yaw, pitch] => Ok(Keyframe {
+                        time,
+                        position: Vec3::new(x, y, z),
+                        yaw: Rad(yaw),
+                        pitch: Rad(pitch),
+                    }),
+                    _ => bail!("{}:{}: Expected `time x y z yaw pitch`", path.display(), n),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        ensure!(!keyframes.is_empty(), "{} has no keyframes", path.display());
+        ensure!(
+            keyframes.windows(2).all(|pair| pair[0].time < pair[1].time),
+            "{}: Keyframe times must increase",
+            path.display()
+        );
+        Ok(Self { keyframes })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut text = String::from("# time x y z yaw pitch\n");
+        for key in &self.keyframes {
+            text += &format!(
+                "{} {} {} {} {} {}\n",
+                key.time, key.position.x, key.position.y, key.position.z, key.yaw.0, key.pitch.0
+            );
+        }
+        write(path, text)?;
+        Ok(())
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |key| key.time)
+    }
+
+    /// The camera at `time` seconds into the path.
+    pub fn sample(&self, time: f32) -> Keyframe {
+        let keys = &self.keyframes;
+        if keys.len() == 1 {
+            return Keyframe { time, ..keys[0] };
+        }
+        let next = keys
+            .iter()
+            .position(|key| key.time > time)
+            .unwrap_or(keys.len() - 1)
+            .max(1);
+        // Repeat the first and last keyframes past the ends
+        let at = |i: usize| keys[i.min(keys.len() - 1)];
+        let (k0, k1, k2, k3) = (at(next.max(2) - 2), at(next - 1), at(next), at(next + 1));
+
+        let span = k2.time - k1.time;
+        let t = if span > 0.0 {
+            ((time - k1.time) / span).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        Keyframe {
+            time,
+            position: catmull_rom(k0.position, k1.position, k2.position, k3.position, t),
+            yaw: catmull_rom_angle(k0.yaw, k1.yaw, k2.yaw, k3.yaw, t),
+            pitch: catmull_rom_angle(k0.pitch, k1.pitch, k2.pitch, k3.pitch, t),
+        }
+    }
+}
+
+/// Takes a keyframe of the camera every `RECORD_INTERVAL` seconds.
+#[derive(Debug, Default)]
+pub struct Recorder {
+    path: CameraPath,
+    elapsed: f32,
+}
+
+impl Recorder {
+    pub fn update(&mut self, camera: &Camera, dt: Duration) {
+        let due = self
+            .path
+            .keyframes
+            .last()
+            .is_none_or(|key| self.elapsed - key.time >= RECORD_INTERVAL);
+        if due {
+            self.path.keyframes.push(Keyframe::of(camera, self.elapsed));
+        }
+        self.elapsed += dt.as_secs_f32();
+    }
+
+    /// Ends the path where `camera` is now.
+    pub fn finish(mut self, camera: &Camera) -> CameraPath {
+        let last_time = self.path.keyframes.last().map(|key| key.time);
+        if last_time.is_none_or(|time| self.elapsed > time) {
+            self.path.keyframes.push(Keyframe::of(camera, self.elapsed));
+        }
+        self.path
+    }
+}
+
+/// Moves the camera along a path in place of the camera controller.
+#[derive(Debug)]
+pub struct Playback {
+    path: CameraPath,
+    elapsed: f32,
+}
+
+impl Playback {
+    pub fn new(path: CameraPath) -> Self {
+        Self { path, elapsed: 0.0 }
+    }
+
+    /// Returns false once the end of the path has been reached.
+    pub fn update_camera(&mut self, camera: &mut Camera, dt: Duration) -> bool {
+        self.elapsed += dt.as_secs_f32();
+        let key = self.path.sample(self.elapsed);
+        camera.position = key.position;
+        camera.yaw = key.yaw;
+        camera.pitch = key.pitch;
+        self.elapsed < self.path.duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    fn keyframe(time: f32, x: f32, yaw: f32) -> Keyframe {
+        Keyframe {
+            time,
+            position: Vec3::new(x, 2.0 * x, -x),
+            yaw: Rad(yaw),
+            pitch: Rad(0.1 * x),
+        }
+    }
+
+    fn path() -> CameraPath {
+        CameraPath {
+            keyframes: vec![
+                keyframe(0.0, 0.0, 0.0),
+                keyframe(1.0, 3.0, 1.0),
+                keyframe(1.5, -2.0, 0.5),
+                keyframe(3.0, 5.0, 2.0),
+            ],
+        }
+    }
+
+    fn temp_file(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!(
+            "{}_{}_{}",
+            env!("CARGO_PKG_NAME"),
+            std::process::id(),
+            name
+        ))
+    }
+
+    fn assert_keyframe(a: Keyframe, b: Keyframe) {
+        assert!(
+            (a.position - b.position).mag() < 1e-4,
+            "{:?} is not {:?}",
+            a,
+            b
+        );
+        assert!((a.yaw.0 - b.yaw.0).abs() < 1e-4, "{:?} is not {:?}", a, b);
+        assert!(
+            (a.pitch.0 - b.pitch.0).abs() < 1e-4,
+            "{:?} is not {:?}",
+            a,
+            b
+        );
+    }
+
+    #[test]
+    fn spline_hits_the_keyframes() {
+        let path = path();
+        for &key in &path.keyframes {
+            assert_keyframe(path.sample(key.time), key);
+        }
+    }
+
+    #[test]
+    fn spline_holds_still_past_the_ends() {
+        let path = path();
+        assert_keyframe(path.sample(-1.0), path.keyframes[0]);
+        assert_keyframe(path.sample(10.0), path.keyframes[3]);
+        assert_eq!(path.duration(), 3.0);
+    }
+
+    #[test]
+    fn spline_turns_the_short_way_round() {
+        let path = CameraPath {
+            keyframes: vec![keyframe(0.0, 0.0, PI - 0.1), keyframe(1.0, 0.0, -PI + 0.1)],
+        };
+        let halfway = path.sample(0.5).yaw;
+        assert!((halfway.wrapped().0 - PI).abs() < 1e-4, "{:?}", halfway);
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let file = temp_file("round_trip.path");
+        let path = path();
+        path.save(&file).unwrap();
+        let loaded = CameraPath::load(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        assert_eq!(loaded.keyframes.len(), path.keyframes.len());
+        for (a, b) in loaded.keyframes.iter().zip(&path.keyframes) {
+            assert_eq!(a.time, b.time);
+            assert_keyframe(*a, *b);
+        }
+    }
+
+    #[test]
+    fn load_rejects_malformed_paths() {
+        for (name, text) in &[
+            ("too_few.path", "0 1 2 3 4\n"),
+            ("not_a_number.path", "0 1 2 3 4 five\n"),
+            ("backwards.path", "1 0 0 0 0 0\n0 0 0 0 0 0\n"),
+            ("empty.path", "# time x y z yaw pitch\n"),
+        ] {
+            let file = temp_file(name);
+            std::fs::write(&file, text).unwrap();
+            let result = CameraPath::load(&file);
+            std::fs::remove_file(&file).unwrap();
+            assert!(result.is_err(), "{} loaded", name);
+        }
+    }
+}
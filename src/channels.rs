@@ -0,0 +1,143 @@
+//! The `.channels` sidecar files that pick what a playground pass reads
+//! through `iChannel0..3`. `toy.channels` is read for `toy.glsl`,
+//! `toy.a.channels` for Buffer A and so on. Each line binds one channel:
+//!
+//! ```text
+//! # Files are relative to this one
+//! iChannel0 buffer_a
+//! iChannel1 textures/noise.png
+//! iChannel2 music.wav
+//! ```
+//!
+//! Channels a sidecar leaves out read black. A pass without a sidecar reads
+//! Buffer A-D through `iChannel0..3`.
+
+use anyhow::*;
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use crate::{audio, texture};
+
+pub const CHANNELS: usize = 4;
+pub const BUFFER_NAMES: [&str; CHANNELS] = ["a", "b", "c", "d"];
+
+/// What a channel reads. Images and audio index into `Inputs`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Channel {
+    Empty,
+    Buffer(usize),
+    Image(usize),
+    Audio(usize),
+}
+
+/// The files bound to channels, loaded once however many passes read them.
+#[derive(Default)]
+pub struct Inputs {
+    images: Vec<(PathBuf, texture::Texture)>,
+    audio: Vec<(PathBuf, audio::Audio)>,
+}
+
+impl Inputs {
+    /// Reads the sidecar of the pass in `pass_path` and loads the files it
+    /// names.
+    pub fn load_channels(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        pass_path: &Path,
+    ) -> Result<[Channel; CHANNELS]> {
+        let config = pass_path.with_extension("channels");
+        if !config.exists() {
+            return Ok([
+                Channel::Buffer(0),
+                Channel::Buffer(1),
+                Channel::Buffer(2),
+                Channel::Buffer(3),
+            ]);
+        }
+        let dir = config.parent().unwrap_or_else(|| Path::new("."));
+
+        let mut channels = [Channel::Empty; CHANNELS];
+        let lines = read_to_string(&config)?;
+        let lines = lines
+            .lines()
+            .enumerate()
+            .map(|(n, line)| (n + 1, line.split('#').next().unwrap_or_default().trim()))
+            .filter(|(_, line)| !line.is_empty());
+        for (n, line) in lines {
+            let at = || format!("{}:{}", config.display(), n);
+            let mut words = line.split_whitespace();
+            let name = words.next().unwrap_or_default();
+            let index = name
+                .strip_prefix("iChannel")
+                .and_then(|index| index.parse::<usize>().ok())
+                .filter(|&index| index < CHANNELS)
+                .with_context(|| format!("{}: Unknown channel `{}`", at(), name))?;
+            let input = words
+                .next()
+                .with_context(|| format!("{}: Missing input for {}", at(), name))?;
+            ensure!(
+                words.next().is_none(),
+                "{}: Unexpected text after the input",
+                at()
+            );
+
+            channels[index] = self
+                .load(device, queue, dir, input)
+                .with_context(|| format!("{}: Unable to load {}", at(), input))?;
+        }
+        Ok(channels)
+    }
+
+    fn load(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        dir: &Path,
+        input: &str,
+    ) -> Result<Channel> {
+        if let Some(name) = input.strip_prefix("buffer_") {
+            let index = BUFFER_NAMES
+                .iter()
+                .position(|&buffer| buffer == name)
+                .with_context(|| format!("There is no buffer `{}`", name))?;
+            return Ok(Channel::Buffer(index));
+        }
+
+        let path = dir.join(input);
+        let is_audio = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("wav"));
+        if is_audio {
+            if let Some(index) = self.audio.iter().position(|(loaded, _)| *loaded == path) {
+                return Ok(Channel::Audio(index));
+            }
+            let audio = audio::Audio::load(device, &path)?;
+            self.audio.push((path, audio));
+            Ok(Channel::Audio(self.audio.len() - 1))
+        } else {
+            if let Some(index) = self.images.iter().position(|(loaded, _)| *loaded == path) {
+                return Ok(Channel::Image(index));
+            }
+            let texture = texture::Texture::load_channel(device, queue, &path)?;
+            self.images.push((path, texture));
+            Ok(Channel::Image(self.images.len() - 1))
+        }
+    }
+
+    /// The texture an image or audio channel samples.
+    pub fn view(&self, channel: Channel) -> Option<&wgpu::TextureView> {
+        match channel {
+            Channel::Image(index) => Some(&self.images[index].1.view),
+            Channel::Audio(index) => Some(&self.audio[index].1.texture.view),
+            Channel::Empty | Channel::Buffer(_) => None,
+        }
+    }
+
+    /// Catches the audio textures up with playback.
+    pub fn update(&mut self, queue: &wgpu::Queue) {
+        for (_, audio) in &mut self.audio {
+            audio.update(queue);
+        }
+    }
+}
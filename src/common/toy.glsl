@@ -0,0 +1,29 @@
+// Shadertoy's inputs, shared by the toy prelude and anything it includes.
+#ifndef COMMON_TOY_GLSL
+#define COMMON_TOY_GLSL
+
+layout(set=0, binding=0)
+uniform ToyUniforms {
+    vec3 iResolution;
+    float iTime;
+    vec4 iMouse;
+    vec4 iDate;
+    float iTimeDelta;
+    int iFrame;
+};
+
+// Buffers, images or audio, or black for a channel with nothing bound. Shadertoy's
+// `sampler2D`s are built from these on use, so the usual `texture(iChannel0,
+// uv)` works, but channels can't be passed to functions.
+layout(set=1, binding=0) uniform texture2D iChannelTexture0;
+layout(set=1, binding=1) uniform texture2D iChannelTexture1;
+layout(set=1, binding=2) uniform texture2D iChannelTexture2;
+layout(set=1, binding=3) uniform texture2D iChannelTexture3;
+layout(set=1, binding=4) uniform sampler iChannelSampler;
+
+#define iChannel0 sampler2D(iChannelTexture0, iChannelSampler)
+#define iChannel1 sampler2D(iChannelTexture1, iChannelSampler)
+#define iChannel2 sampler2D(iChannelTexture2, iChannelSampler)
+#define iChannel3 sampler2D(iChannelTexture3, iChannelSampler)
+
+#endif
@@ -0,0 +1,24 @@
+// Uniform blocks shared by every pipeline. The including shader picks the
+// descriptor sets by defining UNIFORMS_SET and LIGHT_SET beforehand.
+#ifndef COMMON_UNIFORMS_GLSL
+#define COMMON_UNIFORMS_GLSL
+
+#ifdef UNIFORMS_SET
+layout(set=UNIFORMS_SET, binding=0)
+uniform Uniforms {
+    vec3 u_view_position;
+    mat4 u_view_proj;
+    // Index into the instance buffer of the picked instance, or -1
+    int u_selected_instance;
+};
+#endif
+
+#ifdef LIGHT_SET
+layout(set=LIGHT_SET, binding=0)
+uniform Light {
+    vec3 light_position;
+    vec3 light_color;
+};
+#endif
+
+#endif
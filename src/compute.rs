@@ -0,0 +1,49 @@
+//! Compute work that is encoded every frame ahead of the render pass.
+
+/// A compute pipeline together with the bind groups and workgroup count it
+/// is dispatched with.
+pub struct ComputePass {
+    pub pipeline: wgpu::ComputePipeline,
+    pub bind_groups: Vec<wgpu::BindGroup>,
+    pub workgroups: (u32, u32, u32),
+}
+
+impl ComputePass {
+    pub fn dispatch<'a>(&'a self, pass: &mut wgpu::ComputePass<'a>) {
+        pass.set_pipeline(&self.pipeline);
+        for (index, bind_group) in self.bind_groups.iter().enumerate() {
+            pass.set_bind_group(index as u32, bind_group, &[]);
+        }
+        let (x, y, z) = self.workgroups;
+        pass.dispatch(x, y, z);
+    }
+}
+
+/// Number of workgroups of `size` invocations needed to cover `count` items.
+pub fn workgroup_count(count: u32, size: u32) -> u32 {
+    (count + size - 1) / size
+}
+
+/// Binds `resources` to bindings 0, 1, 2... in order. Storage buffers are
+/// bound with `BindingResource::Buffer`, sampled and storage textures with
+/// `BindingResource::TextureView`.
+pub fn create_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    label: &str,
+    resources: Vec<wgpu::BindingResource>,
+) -> wgpu::BindGroup {
+    let entries = resources
+        .into_iter()
+        .enumerate()
+        .map(|(binding, resource)| wgpu::BindGroupEntry {
+            binding: binding as u32,
+            resource,
+        })
+        .collect::<Vec<_>>();
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &entries,
+        label: Some(label),
+    })
+}
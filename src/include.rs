@@ -0,0 +1,157 @@
+//! GLSL `#include` handling shared by `build.rs` and the runtime shader reloader.
+//! Both `"..."` and `<...>` includes are resolved relative to the shader root.
+
+use std::fs::read_to_string;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub fn resolve(root: &Path, requested: &str) -> Result<shaderc::ResolvedInclude, String> {
+    let path = root.join(requested);
+    let content = read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(shaderc::ResolvedInclude {
+        resolved_name: path.to_string_lossy().into_owned(),
+        content,
+    })
+}
+
+fn parse_directive(line: &str) -> Option<&str> {
+    let rest = line.trim_start().strip_prefix('#')?.trim_start();
+    let rest = rest.strip_prefix("include")?.trim();
+    let close = match rest.chars().next()? {
+        '"' => '"',
+        '<' => '>',
+        _ => return None,
+    };
+    let rest = &rest[1..];
+    rest.find(close).map(|end| &rest[..end])
+}
+
+/// Collects every file `src` includes, directly or through other includes.
+pub fn dependencies(root: &Path, src: &str) -> io::Result<Vec<PathBuf>> {
+    let mut found = Vec::new();
+    let mut pending = src
+        .lines()
+        .filter_map(parse_directive)
+        .map(|requested| root.join(requested))
+        .collect::<Vec<_>>();
+
+    while let Some(path) = pending.pop() {
+        if found.contains(&path) {
+            continue;
+        }
+        let content = read_to_string(&path)?;
+        pending.extend(
+            content
+                .lines()
+                .filter_map(parse_directive)
+                .map(|requested| root.join(requested)),
+        );
+        found.push(path);
+    }
+
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, remove_dir_all, write};
+
+    /// A shader root of its own, removed again when dropped.
+    struct Root(PathBuf);
+
+    impl Root {
+        fn new(name: &str, files: &[(&str, &str)]) -> Self {
+            let root = std::env::temp_dir().join(format!(
+                "{}_{}_{}",
+                env!("CARGO_PKG_NAME"),
+                std::process::id(),
+                name
+            ));
+            create_dir_all(&root).unwrap();
+            for (file, content) in files {
+                write(root.join(file), content).unwrap();
+            }
+            Self(root)
+        }
+    }
+
+    impl Drop for Root {
+        fn drop(&mut self) {
+            let _ = remove_dir_all(&self.0);
+        }
+    }
+
+    fn sorted(mut paths: Vec<PathBuf>) -> Vec<PathBuf> {
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn parses_both_kinds_of_directive() {
+        assert_eq!(parse_directive("#include \"a.glsl\""), Some("a.glsl"));
+        assert_eq!(
+            parse_directive("  #  include <b.glsl> // b"),
+            Some("b.glsl")
+        );
+        assert_eq!(parse_directive("#include a.glsl"), None);
+        assert_eq!(parse_directive("#include \"a.glsl"), None);
+        assert_eq!(parse_directive("#define INCLUDE 1"), None);
+        assert_eq!(parse_directive("// #include \"a.glsl\""), None);
+    }
+
+    #[test]
+    fn follows_nested_includes() {
+        let root = Root::new(
+            "nested",
+            &[
+                ("a.glsl", "#include \"b.glsl\"\n"),
+                ("b.glsl", "#include <c.glsl>\n"),
+                ("c.glsl", "float c;\n"),
+            ],
+        );
+        let found = dependencies(&root.0, "#include \"a.glsl\"\nvoid main() {}\n").unwrap();
+        assert_eq!(
+            sorted(found),
+            vec![
+                root.0.join("a.glsl"),
+                root.0.join("b.glsl"),
+                root.0.join("c.glsl")
+            ]
+        );
+    }
+
+    #[test]
+    fn include_cycles_are_listed_once() {
+        let root = Root::new(
+            "cycle",
+            &[
+                ("a.glsl", "#include \"b.glsl\"\n"),
+                ("b.glsl", "#include \"a.glsl\"\n#include \"b.glsl\"\n"),
+            ],
+        );
+        let found = dependencies(&root.0, "#include \"a.glsl\"\n#include \"a.glsl\"\n").unwrap();
+        assert_eq!(
+            sorted(found),
+            vec![root.0.join("a.glsl"), root.0.join("b.glsl")]
+        );
+    }
+
+    #[test]
+    fn missing_includes_are_errors() {
+        let root = Root::new("missing", &[("a.glsl", "#include \"gone.glsl\"\n")]);
+        let error = dependencies(&root.0, "#include \"a.glsl\"\n").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+
+        let error = resolve(&root.0, "gone.glsl").err().unwrap();
+        assert!(error.contains("gone.glsl"), "{}", error);
+    }
+
+    #[test]
+    fn resolves_relative_to_the_root() {
+        let root = Root::new("resolve", &[("a.glsl", "float a;\n")]);
+        let resolved = resolve(&root.0, "a.glsl").unwrap();
+        assert_eq!(resolved.content, "float a;\n");
+        assert_eq!(PathBuf::from(resolved.resolved_name), root.0.join("a.glsl"));
+    }
+}
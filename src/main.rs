@@ -1,4 +1,7 @@
+use std::cmp::Ordering;
 use std::iter;
+use std::mem;
+use std::path::PathBuf;
 
 use wgpu::util::DeviceExt;
 use winit::{
@@ -16,38 +19,49 @@ pub(crate) type Vec3 = ultraviolet::Vec3;
 pub(crate) type Vec2 = ultraviolet::Vec2;
 
 mod angle;
+mod audio;
+mod bookmarks;
+mod bounds;
 mod camera;
+mod camera_path;
+mod channels;
+mod compute;
+mod include;
 mod model;
+// Read by build.rs, compiled here for its tests.
+#[cfg(test)]
+mod permutation_manifest;
+mod permutations;
+mod reflect;
+mod shader;
+mod spirv;
 mod texture;
+mod toy;
+mod vertex_layout;
+mod viewport;
+mod wgsl;
 
 use angle::Deg;
+use camera::{CameraControl, CameraInput};
 
 use model::{DrawLight, DrawModel, Material, Vertex};
 
 const NUM_INSTANCES_PER_ROW: u32 = 10;
+/// Where R saves the recorded camera path and L plays it back from.
+const CAMERA_PATH_FILE: &str = "camera_path.txt";
+/// Pixels the cursor may move between pressing and releasing the left
+/// button for it to still count as a click rather than a drag.
+const CLICK_DISTANCE: f64 = 4.0;
 
-#[repr(C)]
-#[derive(Copy, Clone)]
-struct Uniforms {
-    view_position: Vec4,
-    view_proj: Mat4,
-}
+// `Uniforms`, `Light`, `Animation` and `ToyUniforms`, generated by build.rs from the shaders' uniform blocks
+include!(concat!(env!("OUT_DIR"), "/uniforms.rs"));
 
 impl Uniforms {
-    fn new() -> Self {
-        Self {
-            view_position: Vec4::zero(),
-            view_proj: Mat4::from_scale_4d(0.),
-        }
-    }
-
-    fn update_view_proj(&mut self, camera: &camera::Camera, projection: &camera::Projection) {
-        self.view_position = camera.position.into_homogeneous_point();
-        self.view_proj = projection.calc_matrix() * camera.calc_matrix()
+    fn update_view_proj<C: camera::View>(&mut self, camera: &C, projection: &camera::Projection) {
+        self.u_view_position = camera.position();
+        self.u_view_proj = projection.calc_matrix() * camera.calc_matrix()
     }
 }
-unsafe impl bytemuck::Zeroable for Uniforms {}
-unsafe impl bytemuck::Pod for Uniforms {}
 
 struct Instance {
     position: Vec3,
@@ -63,26 +77,44 @@ impl Instance {
     }
 }
 
+/// The rotation `instances.comp` applies after `time` seconds.
+fn spin(time: f32) -> Mat4 {
+    let (s, c) = time.sin_cos();
+    Mat4::new(
+        Vec4::new(c, 0.0, -s, 0.0),
+        Vec4::unit_y(),
+        Vec4::new(s, 0.0, c, 0.0),
+        Vec4::unit_w(),
+    )
+}
+
 #[derive(Copy, Clone)]
 struct InstanceRaw {
-    #[allow(dead_code)]
     model: Mat4,
 }
 
 unsafe impl bytemuck::Pod for InstanceRaw {}
 unsafe impl bytemuck::Zeroable for InstanceRaw {}
 
-#[repr(C)]
-#[derive(Debug, Copy, Clone)]
-struct Light {
-    position: Vec3,
-    // Due to uniforms requiring 16 byte (4 float) spacing, we need to use a padding field here
-    _padding: u32,
-    color: Vec3,
+/// The bookmark slot a number key stands for.
+fn bookmark_slot(key: VirtualKeyCode) -> Option<usize> {
+    use VirtualKeyCode::*;
+    [Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9]
+        .iter()
+        .position(|&digit| digit == key)
+        .map(|index| index + 1)
 }
 
-unsafe impl bytemuck::Zeroable for Light {}
-unsafe impl bytemuck::Pod for Light {}
+/// The variant `steps` after `shader_frag` in `ShaderFrag::ALL`, wrapping
+/// around.
+fn next_shader_frag(
+    shader_frag: permutations::ShaderFrag,
+    steps: usize,
+) -> permutations::ShaderFrag {
+    let all = permutations::ShaderFrag::ALL;
+    let current = all.iter().position(|&v| v == shader_frag).unwrap_or(0);
+    all[(current + steps) % all.len()]
+}
 
 struct State {
     surface: wgpu::Surface,
@@ -91,32 +123,91 @@ struct State {
     sc_desc: wgpu::SwapChainDescriptor,
     swap_chain: wgpu::SwapChain,
     render_pipeline: wgpu::RenderPipeline,
+    shader_frag: permutations::ShaderFrag,
     obj_model: model::Model,
-    camera: camera::Camera,
-    projection: camera::Projection,
-    camera_controller: camera::CameraController,
+    viewports: Vec<viewport::Viewport>,
+    layout: viewport::Layout,
+    /// The viewport the camera controller moves, and the one recording,
+    /// playback and bookmarks start in.
+    active_viewport: usize,
+    depth_mode: camera::DepthMode,
+    camera_controller: Box<dyn camera::CameraControl>,
+    /// Swapped in for `camera_controller` with Tab.
+    spare_camera_controller: Box<dyn camera::CameraControl>,
+    /// Takes the place of `camera_controller` in viewports flying a free
+    /// camera.
+    free_fly_controller: camera::FreeFlyController,
+    /// Recording, playback and bookmark transitions keep the index of the
+    /// viewport they started in until they finish.
+    recorder: Option<(usize, camera_path::Recorder)>,
+    /// Moves the camera instead of `camera_controller` while it runs.
+    playback: Option<(usize, camera_path::Playback)>,
+    bookmarks: bookmarks::Bookmarks,
+    bookmarks_path: PathBuf,
+    /// Takes precedence over `playback` and `camera_controller`.
+    bookmark_transition: Option<(usize, bookmarks::Transition)>,
+    modifiers: ModifiersState,
     last_mouse_pos: PhysicalPosition<f64>,
     mouse_pressed: bool,
-    uniforms: Uniforms,
-    uniform_buffer: wgpu::Buffer,
-    uniform_bind_group: wgpu::BindGroup,
+    /// How far the cursor has moved since the left button was pressed.
+    mouse_travel: f64,
+    pan_pressed: bool,
+    uniform_bind_group_layout: wgpu::BindGroupLayout,
     instances: Vec<Instance>,
-    #[allow(dead_code)]
+    /// Has room for every instance once per viewport.
     instance_buffer: wgpu::Buffer,
+    /// The untransformed instances `instances.comp` animates from.
+    instance_base_buffer: wgpu::Buffer,
+    selected_instance: Option<usize>,
     depth_texture: texture::Texture,
     size: winit::dpi::PhysicalSize<u32>,
     light: Light,
     light_buffer: wgpu::Buffer,
     light_bind_group: wgpu::BindGroup,
     light_render_pipeline: wgpu::RenderPipeline,
+    render_pipeline_layout: wgpu::PipelineLayout,
+    light_render_pipeline_layout: wgpu::PipelineLayout,
+    shader_watcher: shader::ShaderWatcher,
     debug_material: Material,
+    animation: Animation,
+    animation_buffer: wgpu::Buffer,
+    instance_animation: compute::ComputePass,
+    instance_animation_layout: wgpu::PipelineLayout,
+    animate_instances: bool,
+}
+
+async fn request_device(
+    window: &Window,
+) -> Result<(wgpu::Surface, wgpu::Device, wgpu::Queue), Box<dyn std::error::Error>> {
+    // The instance is a handle to our GPU
+    // BackendBit::PRIMARY => Vulkan + Metal + DX12 + Browser WebGPU
+    let instance = wgpu::Instance::new(wgpu::BackendBit::PRIMARY);
+    let surface = unsafe { instance.create_surface(window) };
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::Default,
+            compatible_surface: Some(&surface),
+        })
+        .await
+        .ok_or("Can't create surface from a raw window handler.")?;
+    let (device, queue) = adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                features: wgpu::Features::empty(),
+                limits: wgpu::Limits::default(),
+                shader_validation: true,
+            },
+            None, // Trace path
+        )
+        .await?;
+    Ok((surface, device, queue))
 }
 
 fn create_render_pipeline(
     device: &wgpu::Device,
     layout: &wgpu::PipelineLayout,
     color_format: wgpu::TextureFormat,
-    depth_format: Option<wgpu::TextureFormat>,
+    depth_mode: Option<camera::DepthMode>,
     vertex_descs: &[wgpu::VertexBufferDescriptor],
     vs_src: wgpu::ShaderModuleSource,
     fs_src: wgpu::ShaderModuleSource,
@@ -150,10 +241,10 @@ fn create_render_pipeline(
             alpha_blend: wgpu::BlendDescriptor::REPLACE,
             write_mask: wgpu::ColorWrite::ALL,
         }],
-        depth_stencil_state: depth_format.map(|format| wgpu::DepthStencilStateDescriptor {
-            format,
+        depth_stencil_state: depth_mode.map(|depth_mode| wgpu::DepthStencilStateDescriptor {
+            format: texture::Texture::DEPTH_FORMAT,
             depth_write_enabled: true,
-            depth_compare: wgpu::CompareFunction::Less,
+            depth_compare: depth_mode.compare(),
             stencil: wgpu::StencilStateDescriptor::default(),
         }),
         sample_count: 1,
@@ -166,31 +257,53 @@ fn create_render_pipeline(
     })
 }
 
+fn create_compute_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    cs_src: wgpu::ShaderModuleSource,
+) -> wgpu::ComputePipeline {
+    let cs_module = device.create_shader_module(cs_src);
+
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some("Compute Pipeline"),
+        layout: Some(&layout),
+        compute_stage: wgpu::ProgrammableStageDescriptor {
+            module: &cs_module,
+            entry_point: "main",
+        },
+    })
+}
+
+/// Recompiles the GLSL sources from disk and builds a fresh pipeline from them.
+fn recompile_render_pipeline(
+    device: &wgpu::Device,
+    shader_watcher: &mut shader::ShaderWatcher,
+    layout: &wgpu::PipelineLayout,
+    color_format: wgpu::TextureFormat,
+    depth_mode: camera::DepthMode,
+    vs_name: &str,
+    fs_name: &str,
+    fs_defines: &[(&str, Option<&str>)],
+) -> anyhow::Result<wgpu::RenderPipeline> {
+    let vs_src = shader_watcher.compile(vs_name, &[])?;
+    let fs_src = shader_watcher.compile(fs_name, fs_defines)?;
+    log::info!("Reloaded {} and {}", vs_name, fs_name);
+
+    Ok(create_render_pipeline(
+        device,
+        layout,
+        color_format,
+        Some(depth_mode),
+        &[model::ModelVertex::desc()],
+        vs_src,
+        fs_src,
+    ))
+}
+
 impl State {
     async fn new(window: &Window) -> Result<Self, Box<dyn std::error::Error>> {
         let size = window.inner_size();
-
-        // The instance is a handle to our GPU
-        // BackendBit::PRIMARY => Vulkan + Metal + DX12 + Browser WebGPU
-        let instance = wgpu::Instance::new(wgpu::BackendBit::PRIMARY);
-        let surface = unsafe { instance.create_surface(window) };
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::Default,
-                compatible_surface: Some(&surface),
-            })
-            .await
-            .ok_or("Can't create surface from a raw window handler.")?;
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    features: wgpu::Features::empty(),
-                    limits: wgpu::Limits::default(),
-                    shader_validation: true,
-                },
-                None, // Trace path
-            )
-            .await?;
+        let (surface, device, queue) = request_device(window).await?;
 
         let sc_desc = wgpu::SwapChainDescriptor {
             usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
@@ -202,58 +315,24 @@ impl State {
 
         let swap_chain = device.create_swap_chain(&surface, &sc_desc);
 
+        // The main pipeline's shaders declare every binding the light
+        // pipeline uses too, so its layouts are shared by both.
+        let layout = reflect::Layout::from_spirv(&[
+            &include_bytes!("shader.vert.sprv")[..],
+            &include_bytes!("shader.frag.sprv")[..],
+        ])?;
+
         let texture_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                entries: &[
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStage::FRAGMENT,
-                        ty: wgpu::BindingType::SampledTexture {
-                            multisampled: false,
-                            component_type: wgpu::TextureComponentType::Float,
-                            dimension: wgpu::TextureViewDimension::D2,
-                        },
-                        count: None,
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 1,
-                        visibility: wgpu::ShaderStage::FRAGMENT,
-                        ty: wgpu::BindingType::Sampler { comparison: false },
-                        count: None,
-                    },
-                    // normal map
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 2,
-                        visibility: wgpu::ShaderStage::FRAGMENT,
-                        ty: wgpu::BindingType::SampledTexture {
-                            multisampled: false,
-                            component_type: wgpu::TextureComponentType::Float,
-                            dimension: wgpu::TextureViewDimension::D2,
-                        },
-                        count: None,
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 3,
-                        visibility: wgpu::ShaderStage::FRAGMENT,
-                        ty: wgpu::BindingType::Sampler { comparison: false },
-                        count: None,
-                    },
-                ],
-                label: Some("texture_bind_group_layout"),
-            });
+            layout.create_bind_group_layout(&device, 0, "texture_bind_group_layout");
         let camera = camera::Camera::new((0.0, 5.0, 10.0), Deg(-90.0), Deg(-20.0));
-        let projection =
+        let depth_mode = camera::DepthMode::ReverseZ;
+        let mut projection =
             camera::Projection::new(sc_desc.width, sc_desc.height, Deg(45.0), 0.1, 100.0);
-        let camera_controller = camera::CameraController::new(4.0, 0.4);
-
-        let mut uniforms = Uniforms::new();
-        uniforms.update_view_proj(&camera, &projection);
-
-        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Uniform Buffer"),
-            contents: bytemuck::cast_slice(&[uniforms]),
-            usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
-        });
+        projection.depth_mode = depth_mode;
+        let camera_controller = Box::new(camera::CameraController::new(4.0, 0.005));
+        let spare_camera_controller =
+            Box::new(camera::OrbitController::new(Vec3::zero(), 10.0, 0.005));
+        let free_fly_controller = camera::FreeFlyController::new(4.0, 0.005);
 
         const SPACE_BETWEEN: f32 = 3.0;
         let instances = (0..NUM_INSTANCES_PER_ROW)
@@ -278,70 +357,46 @@ impl State {
             })
             .collect::<Vec<_>>();
 
-        let instance_data = instances.iter().map(Instance::to_raw).collect::<Vec<_>>();
+        let instance_data = (0..viewport::Layout::MAX_VIEWPORTS)
+            .flat_map(|_| instances.iter().map(Instance::to_raw))
+            .collect::<Vec<_>>();
         let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Instance Buffer"),
             contents: bytemuck::cast_slice(&instance_data),
-            usage: wgpu::BufferUsage::STORAGE,
+            usage: wgpu::BufferUsage::STORAGE | wgpu::BufferUsage::COPY_DST,
+        });
+        let instance_base_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Instance Base Buffer"),
+            contents: bytemuck::cast_slice(&instance_data),
+            usage: wgpu::BufferUsage::STORAGE | wgpu::BufferUsage::COPY_DST,
         });
 
         let uniform_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                entries: &[
-                    // Camera uniforms
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStage::VERTEX | wgpu::ShaderStage::FRAGMENT,
-                        ty: wgpu::BindingType::UniformBuffer {
-                            dynamic: false,
-                            min_binding_size: None,
-                        },
-                        count: None,
-                    },
-                    // Instances
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 1,
-                        visibility: wgpu::ShaderStage::VERTEX,
-                        ty: wgpu::BindingType::StorageBuffer {
-                            dynamic: false,
-                            readonly: true,
-                            min_binding_size: None,
-                        },
-                        count: None,
-                    },
-                ],
-                label: Some("uniform_bind_group_layout"),
-            });
+            layout.create_bind_group_layout(&device, 1, "uniform_bind_group_layout");
 
-        let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &uniform_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::Buffer(uniform_buffer.slice(..)),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Buffer(instance_buffer.slice(..)),
-                },
-            ],
-            label: Some("uniform_bind_group"),
-        });
+        let layout_kind = viewport::Layout::Single;
+        let viewports = vec![viewport::Viewport::new(
+            &device,
+            &uniform_bind_group_layout,
+            &instance_buffer,
+            camera,
+            projection,
+            layout_kind.rect(0, sc_desc.width, sc_desc.height),
+            0,
+        )];
 
         let res_dir = std::path::Path::new(env!("OUT_DIR")).join("res");
-        let obj_model = model::Model::load(
-            &device,
-            &queue,
-            &texture_bind_group_layout,
-            res_dir.join("cube.obj"),
-        )
-        .unwrap();
+        let scene_path = res_dir.join("cube.obj");
+        let obj_model =
+            model::Model::load(&device, &queue, &texture_bind_group_layout, &scene_path).unwrap();
 
-        let light = Light {
-            position: (2.0, 2.0, 2.0).into(),
-            _padding: 0,
-            color: (1.0, 1.0, 1.0).into(),
-        };
+        let bookmarks_path = bookmarks::file_for(&scene_path);
+        let bookmarks = bookmarks::Bookmarks::load(&bookmarks_path).unwrap_or_else(|e| {
+            log::error!("Unable to load the bookmarks: {}", e);
+            bookmarks::Bookmarks::default()
+        });
+
+        let light = Light::new((2.0, 2.0, 2.0).into(), (1.0, 1.0, 1.0).into());
 
         let light_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Light VB"),
@@ -350,18 +405,7 @@ impl State {
         });
 
         let light_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStage::VERTEX | wgpu::ShaderStage::FRAGMENT,
-                    ty: wgpu::BindingType::UniformBuffer {
-                        dynamic: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                }],
-                label: None,
-            });
+            layout.create_bind_group_layout(&device, 2, "light_bind_group_layout");
 
         let light_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             layout: &light_bind_group_layout,
@@ -386,33 +430,41 @@ impl State {
                 push_constant_ranges: &[],
             });
 
+        let shader_frag = permutations::ShaderFrag::Lit;
         let render_pipeline = create_render_pipeline(
             &device,
             &render_pipeline_layout,
             sc_desc.format,
-            Some(texture::Texture::DEPTH_FORMAT),
+            Some(depth_mode),
             &[model::ModelVertex::desc()],
             wgpu::include_spirv!("shader.vert.sprv"),
-            wgpu::include_spirv!("shader.frag.sprv"),
+            shader_frag.spirv(),
         );
 
-        let light_render_pipeline = {
-            let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        let light_render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 label: Some("Light Pipeline Layout"),
                 bind_group_layouts: &[&uniform_bind_group_layout, &light_bind_group_layout],
                 push_constant_ranges: &[],
             });
 
-            create_render_pipeline(
-                &device,
-                &layout,
-                sc_desc.format,
-                Some(texture::Texture::DEPTH_FORMAT),
-                &[model::ModelVertex::desc()],
-                wgpu::include_spirv!("light.vert.sprv"),
-                wgpu::include_spirv!("light.frag.sprv"),
-            )
-        };
+        let light_render_pipeline = create_render_pipeline(
+            &device,
+            &light_render_pipeline_layout,
+            sc_desc.format,
+            Some(depth_mode),
+            &[model::ModelVertex::desc()],
+            wgpu::include_spirv!("light.vert.sprv"),
+            wgpu::include_spirv!("light.frag.sprv"),
+        );
+
+        let shader_watcher = shader::ShaderWatcher::new(&[
+            "shader.vert",
+            "shader.frag",
+            "light.vert",
+            "light.frag",
+            "instances.comp",
+        ])?;
 
         let debug_material = {
             let diffuse_bytes = include_bytes!("../res/cobble-diffuse.png");
@@ -444,47 +496,596 @@ impl State {
             )
         };
 
-        Ok(Self {
+        let animation = Animation::new(0.0, instances.len() as u32, [0; 4]);
+        let animation_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Animation Buffer"),
+            contents: bytemuck::cast_slice(&[animation]),
+            usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+        });
+
+        let compute_layout =
+            reflect::Layout::from_spirv(&[&include_bytes!("instances.comp.sprv")[..]])?;
+        let animation_bind_group_layout =
+            compute_layout.create_bind_group_layout(&device, 0, "animation_bind_group_layout");
+        let animation_bind_group = compute::create_bind_group(
+            &device,
+            &animation_bind_group_layout,
+            "animation_bind_group",
+            vec![
+                wgpu::BindingResource::Buffer(animation_buffer.slice(..)),
+                wgpu::BindingResource::Buffer(instance_base_buffer.slice(..)),
+                wgpu::BindingResource::Buffer(instance_buffer.slice(..)),
+            ],
+        );
+        let instance_animation_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Instance Animation Pipeline Layout"),
+                bind_group_layouts: &[&animation_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let instance_animation = compute::ComputePass {
+            pipeline: create_compute_pipeline(
+                &device,
+                &instance_animation_layout,
+                wgpu::include_spirv!("instances.comp.sprv"),
+            ),
+            bind_groups: vec![animation_bind_group],
+            workgroups: (0, 1, 1),
+        };
+
+        let mut state = Self {
             surface,
             device,
             queue,
             sc_desc,
             swap_chain,
             render_pipeline,
+            shader_frag,
             obj_model,
-            camera,
-            projection,
+            viewports,
+            layout: layout_kind,
+            active_viewport: 0,
+            depth_mode,
             camera_controller,
+            spare_camera_controller,
+            free_fly_controller,
+            recorder: None,
+            playback: None,
+            bookmarks,
+            bookmarks_path,
+            bookmark_transition: None,
+            modifiers: ModifiersState::empty(),
             last_mouse_pos: (0.0, 0.0).into(),
             mouse_pressed: false,
-            uniform_buffer,
-            uniform_bind_group,
-            uniforms,
+            mouse_travel: 0.0,
+            pan_pressed: false,
+            uniform_bind_group_layout,
             instances,
             instance_buffer,
+            instance_base_buffer,
+            selected_instance: None,
             depth_texture,
             size,
             light,
             light_buffer,
             light_bind_group,
             light_render_pipeline,
+            render_pipeline_layout,
+            light_render_pipeline_layout,
+            shader_watcher,
 
             debug_material,
-        })
+            animation,
+            animation_buffer,
+            instance_animation,
+            instance_animation_layout,
+            animate_instances: false,
+        };
+        // The camera starts out looking at everything, whatever the model's size
+        state.frame_instances();
+        Ok(state)
     }
 
     fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
-        self.projection.resize(new_size.width, new_size.height);
         self.size = new_size;
         self.sc_desc.width = new_size.width;
         self.sc_desc.height = new_size.height;
         self.swap_chain = self.device.create_swap_chain(&self.surface, &self.sc_desc);
         self.depth_texture =
             texture::Texture::create_depth_texture(&self.device, &self.sc_desc, "depth_texture");
+        for (index, viewport) in self.viewports.iter_mut().enumerate() {
+            viewport.resize(self.layout.rect(index, new_size.width, new_size.height));
+        }
+    }
+
+    fn active(&self) -> &viewport::Viewport {
+        &self.viewports[self.active_viewport]
+    }
+
+    fn active_mut(&mut self) -> &mut viewport::Viewport {
+        &mut self.viewports[self.active_viewport]
+    }
+
+    /// The controller flying the active viewport's camera, whichever kind
+    /// of camera that is.
+    fn active_controller(&mut self) -> &mut dyn CameraInput {
+        if self.viewports[self.active_viewport].free_camera.is_some() {
+            &mut self.free_fly_controller
+        } else {
+            &mut self.camera_controller
+        }
+    }
+
+    /// Makes the viewport under the cursor the active one.
+    fn activate_viewport_under_cursor(&mut self) {
+        let (x, y) = (self.last_mouse_pos.x, self.last_mouse_pos.y);
+        if let Some(index) = self.viewports.iter().position(|v| v.rect.contains(x, y)) {
+            if index != self.active_viewport {
+                self.active_viewport = index;
+                self.take_over_camera(index);
+            }
+        }
+    }
+
+    /// Lets the controllers pick up from wherever something else left the
+    /// camera of viewport `index`, if it is the one they move.
+    fn take_over_camera(&mut self, index: usize) {
+        if index == self.active_viewport {
+            let viewport = &self.viewports[index];
+            match &viewport.free_camera {
+                Some(free_camera) => self.free_fly_controller.take_over(free_camera),
+                None => self.camera_controller.take_over(&viewport.camera),
+            }
+        }
+    }
+
+    /// Splits the window the next way. Viewports that get added start out
+    /// as copies of the active one, each shaded with the next variant of
+    /// `shader.frag` so they can be compared.
+    fn cycle_layout(&mut self) {
+        self.layout = self.layout.next();
+        let count = self.layout.count();
+        if matches!(self.bookmark_transition, Some((index, _)) if index >= count) {
+            self.bookmark_transition = None;
+        }
+        if matches!(self.playback, Some((index, _)) if index >= count) {
+            self.playback = None;
+        }
+        if matches!(self.recorder, Some((index, _)) if index >= count) {
+            // Save what was recorded before its viewport goes away
+            self.toggle_recording();
+        }
+        self.viewports.truncate(count);
+        self.active_viewport = self.active_viewport.min(count - 1);
+
+        let (width, height) = (self.sc_desc.width, self.sc_desc.height);
+        for (index, viewport) in self.viewports.iter_mut().enumerate() {
+            viewport.resize(self.layout.rect(index, width, height));
+        }
+        while self.viewports.len() < count {
+            let index = self.viewports.len();
+            let mut viewport = viewport::Viewport::new(
+                &self.device,
+                &self.uniform_bind_group_layout,
+                &self.instance_buffer,
+                self.active().camera.clone(),
+                self.active().projection.clone(),
+                self.layout.rect(index, width, height),
+                (index * self.instances.len()) as u32,
+            );
+            let shader_frag = next_shader_frag(self.shader_frag, index);
+            viewport.pipeline = Some(self.shader_frag_pipeline(shader_frag));
+            viewport.shader_frag = Some(shader_frag);
+            self.viewports.push(viewport);
+        }
+        log::info!("Using the {:?} layout", self.layout);
+    }
+
+    /// A main pipeline shaded with `shader_frag`.
+    fn shader_frag_pipeline(&self, shader_frag: permutations::ShaderFrag) -> wgpu::RenderPipeline {
+        create_render_pipeline(
+            &self.device,
+            &self.render_pipeline_layout,
+            self.sc_desc.format,
+            Some(self.depth_mode),
+            &[model::ModelVertex::desc()],
+            wgpu::include_spirv!("shader.vert.sprv"),
+            shader_frag.spirv(),
+        )
+    }
+
+    /// Cycles the variant of `shader.frag` the active viewport is shaded
+    /// with, which is the main pipeline's unless the viewport overrides it.
+    fn cycle_shader_frag(&mut self) {
+        match self.active().shader_frag {
+            Some(shader_frag) => {
+                let shader_frag = next_shader_frag(shader_frag, 1);
+                let pipeline = self.shader_frag_pipeline(shader_frag);
+                let viewport = self.active_mut();
+                viewport.shader_frag = Some(shader_frag);
+                viewport.pipeline = Some(pipeline);
+                log::info!(
+                    "Using {:?} in viewport {}",
+                    shader_frag,
+                    self.active_viewport
+                );
+            }
+            None => {
+                self.shader_frag = next_shader_frag(self.shader_frag, 1);
+                self.render_pipeline = self.shader_frag_pipeline(self.shader_frag);
+                log::info!("Using {:?}", self.shader_frag);
+            }
+        }
+    }
+
+    /// Packs the instances each viewport's camera can see into its part of
+    /// the instance buffer. While they are animated they go to the base
+    /// buffer instead, for `instances.comp` to animate. The highlight follows
+    /// the selected instance to wherever it lands.
+    fn cull_instances(&mut self) {
+        let meshes = &self.obj_model.meshes;
+        // The animation can turn an instance any way about its origin
+        let radius = meshes
+            .iter()
+            .map(|mesh| mesh.bounds.radius_about_origin())
+            .fold(0.0, f32::max);
+        let animate = self.animate_instances;
+        let selected = self.selected_instance;
+        // Viewports that were removed have nothing left to animate
+        self.animation.u_visible_instances = [0; viewport::Layout::MAX_VIEWPORTS];
+        let buffer = if animate {
+            &self.instance_base_buffer
+        } else {
+            &self.instance_buffer
+        };
+
+        for (viewport_index, viewport) in self.viewports.iter_mut().enumerate() {
+            let frustum = bounds::Frustum::from_matrix(&viewport.uniforms.u_view_proj);
+            let visible = self
+                .instances
+                .iter()
+                .enumerate()
+                .filter_map(|(index, instance)| {
+                    let raw = instance.to_raw();
+                    let visible = if animate {
+                        frustum.intersects_sphere(instance.position, radius)
+                    } else {
+                        meshes.iter().any(|mesh| {
+                            frustum.intersects_aabb(&mesh.bounds.transformed(&raw.model))
+                        })
+                    };
+                    if visible {
+                        Some((index, raw))
+                    } else {
+                        None
+                    }
+                })
+                .collect::<Vec<_>>();
+            let slot = visible
+                .iter()
+                .position(|(index, _)| Some(*index) == selected);
+            viewport.uniforms.u_selected_instance =
+                slot.map_or(-1, |slot| (viewport.first_instance as usize + slot) as i32);
+            let visible = visible.into_iter().map(|(_, raw)| raw).collect::<Vec<_>>();
+
+            let offset = viewport.first_instance as usize * mem::size_of::<InstanceRaw>();
+            self.queue.write_buffer(
+                buffer,
+                offset as wgpu::BufferAddress,
+                bytemuck::cast_slice(&visible),
+            );
+
+            let count = visible.len() as u32;
+            if count != viewport.visible_instances {
+                log::debug!(
+                    "{} of {} instances visible in viewport {}",
+                    count,
+                    self.instances.len(),
+                    viewport_index
+                );
+            }
+            viewport.visible_instances = count;
+            self.animation.u_visible_instances[viewport_index] = count;
+        }
+
+        // Covers the parts of the buffers in use. The shader skips the
+        // culled away slots.
+        let used = self.viewports.len() * self.instances.len();
+        self.instance_animation.workgroups.0 = compute::workgroup_count(used as u32, 64);
+    }
+
+    /// Where `instance` is this frame, animation included.
+    fn instance_model(&self, instance: &Instance) -> Mat4 {
+        let model = instance.to_raw().model;
+        if self.animate_instances {
+            model * spin(self.animation.u_time)
+        } else {
+            model
+        }
+    }
+
+    /// The bounds of `instance` in world space.
+    fn instance_bounds(&self, instance: &Instance) -> bounds::Aabb {
+        self.obj_model
+            .bounds
+            .transformed(&self.instance_model(instance))
+    }
+
+    /// The selected instance, or all of them when none is selected.
+    fn focus_bounds(&self) -> bounds::Aabb {
+        match self.selected_instance {
+            Some(index) => self.instance_bounds(&self.instances[index]),
+            None => self
+                .instances
+                .iter()
+                .fold(bounds::Aabb::empty(), |bounds, instance| {
+                    bounds.union(&self.instance_bounds(instance))
+                }),
+        }
+    }
+
+    /// How far ahead of the active camera whatever it looks at is: the orbit
+    /// distance while orbiting, otherwise the depth of what F would frame.
+    fn focus_distance(&self) -> f32 {
+        let viewport = self.active();
+        if viewport.free_camera.is_none() {
+            if let Some(distance) = self.camera_controller.focus_distance() {
+                return distance;
+            }
+        }
+        let offset = self.focus_bounds().center() - viewport.camera.position;
+        // Something behind the camera has no depth to keep the size of
+        let depth = offset.dot(viewport.camera.forward());
+        if depth > 0.0 {
+            depth
+        } else {
+            offset.mag()
+        }
+    }
+
+    /// Frames the selected instance, or all of them when none is selected.
+    fn frame_instances(&mut self) {
+        let bounds = self.focus_bounds();
+        if bounds.is_empty() {
+            return;
+        }
+
+        let active = self.active_viewport;
+        if matches!(self.playback, Some((index, _)) if index == active) {
+            self.playback = None;
+        }
+        if matches!(self.bookmark_transition, Some((index, _)) if index == active) {
+            self.bookmark_transition = None;
+        }
+        let viewport = &mut self.viewports[active];
+        viewport.projection.frame(&mut viewport.camera, &bounds);
+        viewport.follow_camera();
+        self.take_over_camera(active);
+        self.camera_controller
+            .focus(&self.viewports[active].camera, bounds.center());
+    }
+
+    /// Selects the instance under the cursor in the active viewport, or
+    /// nothing when the cursor is over empty space.
+    fn pick_instance(&mut self) {
+        let viewport = self.active();
+        let rect = viewport.rect;
+        let ndc = Vec2::new(
+            2.0 * (self.last_mouse_pos.x as f32 - rect.x as f32) / rect.width as f32 - 1.0,
+            1.0 - 2.0 * (self.last_mouse_pos.y as f32 - rect.y as f32) / rect.height as f32,
+        );
+        let ray = bounds::Ray::unproject(
+            &viewport.uniforms.u_view_proj,
+            ndc,
+            self.depth_mode.near_value(),
+        );
+        let meshes = &self.obj_model.meshes;
+        let nearest = self
+            .instances
+            .iter()
+            .enumerate()
+            .filter_map(|(index, instance)| {
+                let model_ray = ray.transformed(&self.instance_model(instance).inversed());
+                meshes
+                    .iter()
+                    .filter_map(|mesh| mesh.intersect_ray(&model_ray))
+                    .min_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+                    .map(|t| (index, t))
+            })
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        self.selected_instance = nearest.map(|(index, _)| index);
+        log::info!("Selected instance {:?}", self.selected_instance);
+    }
+
+    /// Switches the active viewport between its yaw and pitch camera and a
+    /// free camera that can roll.
+    fn toggle_free_camera(&mut self) {
+        let viewport = &mut self.viewports[self.active_viewport];
+        match viewport.free_camera.take() {
+            // `camera` has been following it all along
+            Some(_) => log::info!("Flying the yaw and pitch camera"),
+            None => {
+                viewport.free_camera = Some(camera::FreeCamera::from_camera(&viewport.camera));
+                log::info!("Flying the free camera, Q and E roll it");
+            }
+        }
+        self.take_over_camera(self.active_viewport);
+    }
+
+    fn toggle_recording(&mut self) {
+        match self.recorder.take() {
+            Some((index, recorder)) => match recorder
+                .finish(&self.viewports[index].camera)
+                .save(CAMERA_PATH_FILE)
+            {
+                Ok(()) => log::info!("Saved the camera path to {}", CAMERA_PATH_FILE),
+                Err(e) => log::error!("Unable to save the camera path: {}", e),
+            },
+            None => {
+                log::info!("Recording the camera path");
+                self.recorder = Some((self.active_viewport, camera_path::Recorder::default()));
+            }
+        }
+    }
+
+    fn play_camera_path(&mut self) {
+        match camera_path::CameraPath::load(CAMERA_PATH_FILE) {
+            Ok(path) => {
+                log::info!("Playing {}", CAMERA_PATH_FILE);
+                self.playback = Some((self.active_viewport, camera_path::Playback::new(path)));
+            }
+            Err(e) => log::error!("Unable to load the camera path: {}", e),
+        }
+    }
+
+    /// Ctrl + the slot's number saves the view there, the number on its own
+    /// flies back to it.
+    fn use_bookmark(&mut self, slot: usize) {
+        let viewport = self.active();
+        let here = bookmarks::Bookmark::of(&viewport.camera, &viewport.projection);
+        if self.modifiers.ctrl() {
+            self.bookmarks.set(slot, here);
+            match self.bookmarks.save(&self.bookmarks_path) {
+                Ok(()) => log::info!(
+                    "Saved bookmark {} to {}",
+                    slot,
+                    self.bookmarks_path.display()
+                ),
+                Err(e) => log::error!("Unable to save the bookmarks: {}", e),
+            }
+        } else if let Some(bookmark) = self.bookmarks.get(slot) {
+            let active = self.active_viewport;
+            if matches!(self.playback, Some((index, _)) if index == active) {
+                self.playback = None;
+            }
+            self.bookmark_transition = Some((active, bookmarks::Transition::new(here, bookmark)));
+        } else {
+            log::info!("Bookmark {} is empty", slot);
+        }
     }
 
     fn input(&mut self, event: &WindowEvent) -> bool {
         match event {
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = *modifiers;
+                true
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        virtual_keycode: Some(VirtualKeyCode::P),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                self.cycle_shader_frag();
+                true
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        virtual_keycode: Some(VirtualKeyCode::V),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                self.cycle_layout();
+                true
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        virtual_keycode: Some(VirtualKeyCode::I),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                self.animate_instances = !self.animate_instances;
+                true
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        virtual_keycode: Some(VirtualKeyCode::O),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                let focus_distance = self.focus_distance();
+                let viewport = self.active_mut();
+                viewport.projection.toggle_orthographic(focus_distance);
+                log::info!("Using {:?} projection", viewport.projection.mode);
+                true
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        virtual_keycode: Some(VirtualKeyCode::F),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                self.frame_instances();
+                true
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        virtual_keycode: Some(VirtualKeyCode::C),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                self.toggle_free_camera();
+                true
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        virtual_keycode: Some(VirtualKeyCode::R),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                self.toggle_recording();
+                true
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        virtual_keycode: Some(VirtualKeyCode::L),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                self.play_camera_path();
+                true
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        virtual_keycode: Some(VirtualKeyCode::Tab),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                mem::swap(
+                    &mut self.camera_controller,
+                    &mut self.spare_camera_controller,
+                );
+                self.camera_controller
+                    .take_over(&self.viewports[self.active_viewport].camera);
+                true
+            }
             WindowEvent::KeyboardInput {
                 input:
                     KeyboardInput {
@@ -493,9 +1094,15 @@ impl State {
                         ..
                     },
                 ..
-            } => self.camera_controller.process_keyboard(*key, *state),
+            } => match bookmark_slot(*key) {
+                Some(slot) if *state == ElementState::Pressed => {
+                    self.use_bookmark(slot);
+                    true
+                }
+                _ => self.active_controller().process_keyboard(*key, *state),
+            },
             WindowEvent::MouseWheel { delta, .. } => {
-                self.camera_controller.process_scroll(delta);
+                self.active_controller().process_scroll(delta);
                 true
             }
             WindowEvent::MouseInput {
@@ -504,6 +1111,23 @@ impl State {
                 ..
             } => {
                 self.mouse_pressed = *state == ElementState::Pressed;
+                if self.mouse_pressed {
+                    self.activate_viewport_under_cursor();
+                    self.mouse_travel = 0.0;
+                } else if self.mouse_travel < CLICK_DISTANCE {
+                    self.pick_instance();
+                }
+                true
+            }
+            WindowEvent::MouseInput {
+                button: MouseButton::Middle,
+                state,
+                ..
+            } => {
+                self.pan_pressed = *state == ElementState::Pressed;
+                if self.pan_pressed {
+                    self.activate_viewport_under_cursor();
+                }
                 true
             }
             WindowEvent::CursorMoved { position, .. } => {
@@ -511,7 +1135,11 @@ impl State {
                 let mouse_dy = position.y - self.last_mouse_pos.y;
                 self.last_mouse_pos = *position;
                 if self.mouse_pressed {
-                    self.camera_controller.process_mouse(mouse_dx, mouse_dy);
+                    self.mouse_travel += mouse_dx.hypot(mouse_dy);
+                    self.active_controller().process_mouse(mouse_dx, mouse_dy);
+                }
+                if self.pan_pressed {
+                    self.active_controller().process_pan(mouse_dx, mouse_dy);
                 }
                 true
             }
@@ -519,21 +1147,139 @@ impl State {
         }
     }
 
+    fn reload_shaders(&mut self) {
+        let changed = self.shader_watcher.poll();
+        let touches = |prefix: &str| {
+            changed
+                .iter()
+                .any(|name| name.to_string_lossy().starts_with(prefix))
+        };
+
+        if touches("shader.") {
+            match recompile_render_pipeline(
+                &self.device,
+                &mut self.shader_watcher,
+                &self.render_pipeline_layout,
+                self.sc_desc.format,
+                self.depth_mode,
+                "shader.vert",
+                permutations::ShaderFrag::SOURCE,
+                self.shader_frag.defines(),
+            ) {
+                Ok(pipeline) => self.render_pipeline = pipeline,
+                Err(e) => log::error!("Keeping the last good render pipeline: {}", e),
+            }
+            for viewport in &mut self.viewports {
+                if let Some(shader_frag) = viewport.shader_frag {
+                    match recompile_render_pipeline(
+                        &self.device,
+                        &mut self.shader_watcher,
+                        &self.render_pipeline_layout,
+                        self.sc_desc.format,
+                        self.depth_mode,
+                        "shader.vert",
+                        permutations::ShaderFrag::SOURCE,
+                        shader_frag.defines(),
+                    ) {
+                        Ok(pipeline) => viewport.pipeline = Some(pipeline),
+                        Err(e) => log::error!("Keeping the last good render pipeline: {}", e),
+                    }
+                }
+            }
+        }
+        if touches("light.") {
+            match recompile_render_pipeline(
+                &self.device,
+                &mut self.shader_watcher,
+                &self.light_render_pipeline_layout,
+                self.sc_desc.format,
+                self.depth_mode,
+                "light.vert",
+                "light.frag",
+                &[],
+            ) {
+                Ok(pipeline) => self.light_render_pipeline = pipeline,
+                Err(e) => log::error!("Keeping the last good light pipeline: {}", e),
+            }
+        }
+        if touches("instances.") {
+            match self.shader_watcher.compile("instances.comp", &[]) {
+                Ok(cs_src) => {
+                    log::info!("Reloaded instances.comp");
+                    self.instance_animation.pipeline = create_compute_pipeline(
+                        &self.device,
+                        &self.instance_animation_layout,
+                        cs_src,
+                    );
+                }
+                Err(e) => log::error!("Keeping the last good compute pipeline: {}", e),
+            }
+        }
+    }
+
     fn update(&mut self, dt: std::time::Duration) {
-        self.camera_controller.update_camera(&mut self.camera, dt);
-        self.uniforms
-            .update_view_proj(&self.camera, &self.projection);
-        self.queue.write_buffer(
-            &self.uniform_buffer,
-            0,
-            bytemuck::cast_slice(&[self.uniforms]),
-        );
+        self.reload_shaders();
+        let mut moved = Vec::new();
+        if let Some((index, transition)) = &mut self.bookmark_transition {
+            let index = *index;
+            let viewport = &mut self.viewports[index];
+            let running = transition.update(&mut viewport.camera, &mut viewport.projection, dt);
+            viewport.follow_camera();
+            if !running {
+                self.bookmark_transition = None;
+                self.take_over_camera(index);
+            }
+            moved.push(index);
+        }
+        if let Some((index, playback)) = &mut self.playback {
+            let index = *index;
+            if !moved.contains(&index) {
+                let viewport = &mut self.viewports[index];
+                let running = playback.update_camera(&mut viewport.camera, dt);
+                viewport.follow_camera();
+                if !running {
+                    self.playback = None;
+                    self.take_over_camera(index);
+                }
+                moved.push(index);
+            }
+        }
+        if !moved.contains(&self.active_viewport) {
+            let viewport = &mut self.viewports[self.active_viewport];
+            if let Some(free_camera) = &mut viewport.free_camera {
+                self.free_fly_controller.update_camera(free_camera, dt);
+                viewport.camera = free_camera.to_camera();
+            } else {
+                self.camera_controller
+                    .update_camera(&mut viewport.camera, dt);
+            }
+        }
+        if let Some((index, recorder)) = &mut self.recorder {
+            recorder.update(&self.viewports[*index].camera, dt);
+        }
 
-        let old_light_position = self.light.position;
-        self.light.position =
+        for viewport in &mut self.viewports {
+            viewport.update_view_proj();
+        }
+        self.cull_instances();
+        for viewport in &self.viewports {
+            viewport.write_uniforms(&self.queue);
+        }
+
+        let old_light_position = self.light.light_position;
+        self.light.light_position =
             Rotor3::from_rotation_xz(Deg(1.0 * dt.as_secs_f32()).into()) * old_light_position;
         self.queue
             .write_buffer(&self.light_buffer, 0, bytemuck::cast_slice(&[self.light]));
+
+        if self.animate_instances {
+            self.animation.u_time += dt.as_secs_f32();
+            self.queue.write_buffer(
+                &self.animation_buffer,
+                0,
+                bytemuck::cast_slice(&[self.animation]),
+            );
+        }
     }
 
     fn render(&mut self) {
@@ -549,6 +1295,12 @@ impl State {
                 label: Some("Render Encoder"),
             });
 
+        // Compute work the render pass below depends on
+        if self.animate_instances {
+            let mut compute_pass = encoder.begin_compute_pass();
+            self.instance_animation.dispatch(&mut compute_pass);
+        }
+
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
                 attachment: &frame.view,
@@ -566,28 +1318,41 @@ impl State {
             depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachmentDescriptor {
                 attachment: &self.depth_texture.view,
                 depth_ops: Some(wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(1.0),
+                    load: wgpu::LoadOp::Clear(self.depth_mode.clear_value()),
                     store: true,
                 }),
                 stencil_ops: None,
             }),
         });
 
-        render_pass.set_pipeline(&self.light_render_pipeline);
-        render_pass.draw_light_model(
-            &self.obj_model,
-            &self.uniform_bind_group,
-            &self.light_bind_group,
-        );
+        for viewport in &self.viewports {
+            let rect = viewport.rect;
+            render_pass.set_viewport(
+                rect.x as f32,
+                rect.y as f32,
+                rect.width as f32,
+                rect.height as f32,
+                0.0,
+                1.0,
+            );
+            render_pass.set_scissor_rect(rect.x, rect.y, rect.width, rect.height);
 
-        render_pass.set_pipeline(&self.render_pipeline);
-        render_pass.draw_model_instanced_with_material(
-            &self.obj_model,
-            &self.debug_material,
-            0..self.instances.len() as u32,
-            &self.uniform_bind_group,
-            &self.light_bind_group,
-        );
+            render_pass.set_pipeline(&self.light_render_pipeline);
+            render_pass.draw_light_model(
+                &self.obj_model,
+                &viewport.uniform_bind_group,
+                &self.light_bind_group,
+            );
+
+            render_pass.set_pipeline(viewport.pipeline.as_ref().unwrap_or(&self.render_pipeline));
+            render_pass.draw_model_instanced_with_material(
+                &self.obj_model,
+                &self.debug_material,
+                viewport.first_instance..viewport.first_instance + viewport.visible_instances,
+                &viewport.uniform_bind_group,
+                &self.light_bind_group,
+            );
+        }
 
         drop(render_pass);
 
@@ -595,6 +1360,43 @@ impl State {
     }
 }
 
+/// What the window shows: the model scene, or a Shadertoy style toy when a
+/// GLSL file is passed on the command line.
+enum App {
+    Scene(State),
+    Toy(toy::Toy),
+}
+
+impl App {
+    fn input(&mut self, event: &WindowEvent) -> bool {
+        match self {
+            App::Scene(state) => state.input(event),
+            App::Toy(toy) => toy.input(event),
+        }
+    }
+
+    fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
+        match self {
+            App::Scene(state) => state.resize(new_size),
+            App::Toy(toy) => toy.resize(new_size),
+        }
+    }
+
+    fn update(&mut self, dt: std::time::Duration) {
+        match self {
+            App::Scene(state) => state.update(dt),
+            App::Toy(toy) => toy.update(dt),
+        }
+    }
+
+    fn render(&mut self) {
+        match self {
+            App::Scene(state) => state.render(),
+            App::Toy(toy) => toy.render(),
+        }
+    }
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::init();
     let event_loop = EventLoop::new();
@@ -603,7 +1405,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .with_title(title)
         .build(&event_loop)?;
     use futures::executor::block_on;
-    let mut state = block_on(State::new(&window))?;
+    let mut state = match std::env::args_os().nth(1).map(PathBuf::from) {
+        Some(toy_path) => App::Toy(block_on(toy::Toy::new(&window, &toy_path))?),
+        None => App::Scene(block_on(State::new(&window))?),
+    };
     let mut last_render_time = std::time::Instant::now();
     event_loop.run(move |event, _, control_flow| {
         *control_flow = ControlFlow::Poll;
@@ -1,11 +1,14 @@
 use crate::{Vec2, Vec3};
 use anyhow::*;
 use rayon::prelude::*;
+use std::cmp::Ordering;
 use std::ops::Range;
 use std::path::Path;
 use wgpu::util::DeviceExt;
 
+use crate::bounds::{Aabb, Ray};
 use crate::texture;
+use crate::vertex_layout;
 
 pub trait Vertex {
     fn desc<'a>() -> wgpu::VertexBufferDescriptor<'a>;
@@ -29,34 +32,7 @@ impl Vertex for ModelVertex {
         wgpu::VertexBufferDescriptor {
             stride: mem::size_of::<ModelVertex>() as wgpu::BufferAddress,
             step_mode: wgpu::InputStepMode::Vertex,
-            attributes: &[
-                wgpu::VertexAttributeDescriptor {
-                    offset: 0,
-                    shader_location: 0,
-                    format: wgpu::VertexFormat::Float3,
-                },
-                wgpu::VertexAttributeDescriptor {
-                    offset: mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
-                    shader_location: 1,
-                    format: wgpu::VertexFormat::Float2,
-                },
-                wgpu::VertexAttributeDescriptor {
-                    offset: mem::size_of::<[f32; 5]>() as wgpu::BufferAddress,
-                    shader_location: 2,
-                    format: wgpu::VertexFormat::Float3,
-                },
-                // Tangent and bitangent
-                wgpu::VertexAttributeDescriptor {
-                    offset: mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
-                    shader_location: 3,
-                    format: wgpu::VertexFormat::Float3,
-                },
-                wgpu::VertexAttributeDescriptor {
-                    offset: mem::size_of::<[f32; 11]>() as wgpu::BufferAddress,
-                    shader_location: 4,
-                    format: wgpu::VertexFormat::Float3,
-                },
-            ],
+            attributes: &vertex_layout::MODEL_VERTEX_ATTRIBUTES,
         }
     }
 }
@@ -114,11 +90,36 @@ pub struct Mesh {
     pub index_buffer: wgpu::Buffer,
     pub num_elements: u32,
     pub material: usize,
+    /// In model space.
+    pub bounds: Aabb,
+    /// Kept around for picking.
+    pub positions: Vec<Vec3>,
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    /// How far along `ray`, given in model space, the nearest triangle it
+    /// hits is.
+    pub fn intersect_ray(&self, ray: &Ray) -> Option<f32> {
+        ray.intersect_aabb(&self.bounds)?;
+        self.indices
+            .chunks_exact(3)
+            .filter_map(|c| {
+                ray.intersect_triangle([
+                    self.positions[c[0] as usize],
+                    self.positions[c[1] as usize],
+                    self.positions[c[2] as usize],
+                ])
+            })
+            .min_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+    }
 }
 
 pub struct Model {
     pub meshes: Vec<Mesh>,
     pub materials: Vec<Material>,
+    /// Around every mesh, in model space.
+    pub bounds: Aabb,
 }
 
 impl Model {
@@ -233,11 +234,22 @@ impl Model {
                     index_buffer,
                     num_elements: m.mesh.indices.len() as u32,
                     material: m.mesh.material_id.unwrap_or(0),
+                    bounds: Aabb::from_points(vertices.iter().map(|v| v.position)),
+                    positions: vertices.iter().map(|v| v.position).collect(),
+                    indices: m.mesh.indices.clone(),
                 }
             })
             .collect::<Vec<_>>();
 
-        Ok(Self { meshes, materials })
+        let bounds = meshes
+            .iter()
+            .fold(Aabb::empty(), |bounds, mesh| bounds.union(&mesh.bounds));
+
+        Ok(Self {
+            meshes,
+            materials,
+            bounds,
+        })
     }
 }
 
@@ -0,0 +1,105 @@
+//! Reads `permutations.txt`, the extra define sets `build.rs` compiles
+//! shaders with. One `<shader> <variant> [NAME[=VALUE] ...]` line each, `#`
+//! starting a comment.
+
+use anyhow::*;
+use std::fs::read_to_string;
+
+pub type Defines = Vec<(String, Option<String>)>;
+
+pub struct Permutation {
+    pub shader: String,
+    pub variant: String,
+    pub defines: Defines,
+}
+
+pub fn load(path: &str) -> Result<Vec<Permutation>> {
+    parse(path, &read_to_string(path)?)
+}
+
+/// `path` is only used in error messages.
+pub fn parse(path: &str, manifest: &str) -> Result<Vec<Permutation>> {
+    manifest
+        .lines()
+        .enumerate()
+        .map(|(n, line)| (n + 1, line.split('#').next().unwrap_or_default().trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(n, line)| {
+            let mut words = line.split_whitespace();
+            let shader = words.next().unwrap_or_default().to_owned();
+            let variant = words
+                .next()
+                .with_context(|| format!("{}:{}: Missing variant name", path, n))?
+                .to_owned();
+            let defines = words
+                .map(|define| {
+                    let mut parts = define.splitn(2, '=');
+                    let name = parts.next().unwrap_or_default().to_owned();
+                    (name, parts.next().map(str::to_owned))
+                })
+                .collect();
+
+            Ok(Permutation {
+                shader,
+                variant,
+                defines,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn define(name: &str, value: Option<&str>) -> (String, Option<String>) {
+        (name.to_owned(), value.map(str::to_owned))
+    }
+
+    #[test]
+    fn parses_variants_and_defines() {
+        let permutations = parse(
+            "permutations.txt",
+            "# comment\n\
+             \n\
+             shader.frag lit\n\
+             shader.frag debug DEBUG_NORMALS=1 FLAT # trailing comment\n\
+             light.vert  empty_value  X=\n",
+        )
+        .unwrap();
+
+        assert_eq!(permutations.len(), 3);
+        assert_eq!(permutations[0].shader, "shader.frag");
+        assert_eq!(permutations[0].variant, "lit");
+        assert!(permutations[0].defines.is_empty());
+        assert_eq!(permutations[1].variant, "debug");
+        assert_eq!(
+            permutations[1].defines,
+            vec![define("DEBUG_NORMALS", Some("1")), define("FLAT", None)]
+        );
+        assert_eq!(permutations[2].shader, "light.vert");
+        assert_eq!(permutations[2].defines, vec![define("X", Some(""))]);
+    }
+
+    #[test]
+    fn missing_variant_names_the_line() {
+        let error = parse(
+            "permutations.txt",
+            "shader.frag lit\n\nshader.frag # no variant\n",
+        )
+        .err()
+        .unwrap();
+        assert!(
+            error.to_string().starts_with("permutations.txt:3:"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn the_repo_manifest_parses() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/src/permutations.txt");
+        let permutations = load(path).unwrap();
+        assert!(!permutations.is_empty());
+    }
+}
@@ -0,0 +1,5 @@
+//! Typed handles to the shader permutations listed in `permutations.txt`.
+//! The enums are generated by `build.rs`, one per permuted shader.
+#![allow(dead_code)]
+
+include!(concat!(env!("OUT_DIR"), "/permutations.rs"));
@@ -0,0 +1,187 @@
+use anyhow::*;
+use std::collections::BTreeMap;
+use std::num::NonZeroU32;
+
+use crate::spirv::{self, ImageDim, Type, Variable};
+
+fn visibility(stage: spirv::Stage) -> wgpu::ShaderStage {
+    match stage {
+        spirv::Stage::Vertex => wgpu::ShaderStage::VERTEX,
+        spirv::Stage::Fragment => wgpu::ShaderStage::FRAGMENT,
+        spirv::Stage::Compute => wgpu::ShaderStage::COMPUTE,
+    }
+}
+
+fn view_dimension(dim: ImageDim, arrayed: bool) -> Result<wgpu::TextureViewDimension> {
+    Ok(match (dim, arrayed) {
+        (ImageDim::D1, false) => wgpu::TextureViewDimension::D1,
+        (ImageDim::D2, false) => wgpu::TextureViewDimension::D2,
+        (ImageDim::D2, true) => wgpu::TextureViewDimension::D2Array,
+        (ImageDim::D3, false) => wgpu::TextureViewDimension::D3,
+        (ImageDim::Cube, false) => wgpu::TextureViewDimension::Cube,
+        (ImageDim::Cube, true) => wgpu::TextureViewDimension::CubeArray,
+        _ => bail!(
+            "Unsupported image dimension {:?} (arrayed: {})",
+            dim,
+            arrayed
+        ),
+    })
+}
+
+/// Maps the SPIR-V `ImageFormat` of a storage image to its texture format.
+fn storage_format(format: u32) -> Result<wgpu::TextureFormat> {
+    Ok(match format {
+        1 => wgpu::TextureFormat::Rgba32Float,
+        2 => wgpu::TextureFormat::Rgba16Float,
+        3 => wgpu::TextureFormat::R32Float,
+        4 => wgpu::TextureFormat::Rgba8Unorm,
+        5 => wgpu::TextureFormat::Rgba8Snorm,
+        6 => wgpu::TextureFormat::Rg32Float,
+        21 => wgpu::TextureFormat::Rgba32Sint,
+        23 => wgpu::TextureFormat::Rgba8Sint,
+        24 => wgpu::TextureFormat::R32Sint,
+        30 => wgpu::TextureFormat::Rgba32Uint,
+        32 => wgpu::TextureFormat::Rgba8Uint,
+        33 => wgpu::TextureFormat::R32Uint,
+        _ => bail!("Unsupported storage image format {}", format),
+    })
+}
+
+fn binding_type(variable: &Variable) -> Result<(wgpu::BindingType, Option<NonZeroU32>)> {
+    if variable.is_uniform_buffer() {
+        return Ok((
+            wgpu::BindingType::UniformBuffer {
+                dynamic: false,
+                min_binding_size: None,
+            },
+            None,
+        ));
+    }
+    if variable.is_storage_buffer() {
+        return Ok((
+            wgpu::BindingType::StorageBuffer {
+                dynamic: false,
+                min_binding_size: None,
+                readonly: variable.non_writable,
+            },
+            None,
+        ));
+    }
+
+    let (ty, count) = match &variable.ty {
+        Type::Array {
+            element, length, ..
+        } => (&**element, NonZeroU32::new(*length)),
+        ty => (ty, None),
+    };
+    let binding = match ty {
+        Type::Sampler => wgpu::BindingType::Sampler { comparison: false },
+        Type::Image {
+            storage: false,
+            sampled_type,
+            dim,
+            arrayed,
+            multisampled,
+            ..
+        } => wgpu::BindingType::SampledTexture {
+            dimension: view_dimension(*dim, *arrayed)?,
+            component_type: match **sampled_type {
+                Type::Int { signed: true, .. } => wgpu::TextureComponentType::Sint,
+                Type::Int { signed: false, .. } => wgpu::TextureComponentType::Uint,
+                _ => wgpu::TextureComponentType::Float,
+            },
+            multisampled: *multisampled,
+        },
+        Type::Image {
+            storage: true,
+            dim,
+            arrayed,
+            format,
+            ..
+        } => wgpu::BindingType::StorageTexture {
+            dimension: view_dimension(*dim, *arrayed)?,
+            format: storage_format(*format)?,
+            readonly: variable.non_writable,
+        },
+        Type::SampledImage(_) => {
+            bail!("Combined image samplers are not supported, use separate texture and sampler")
+        }
+        ty => bail!("Unsupported resource type {:?}", ty),
+    };
+    Ok((binding, count))
+}
+
+/// Bind group layouts derived from the resources a set of shader stages
+/// declare. A binding used by several stages is visible to all of them.
+#[derive(Debug, Default)]
+pub struct Layout {
+    sets: BTreeMap<u32, BTreeMap<u32, wgpu::BindGroupLayoutEntry>>,
+}
+
+impl Layout {
+    pub fn from_spirv(stages: &[&[u8]]) -> Result<Self> {
+        let mut layout = Self::default();
+        for stage in stages {
+            layout.add_stage(&spirv::words(stage)?)?;
+        }
+        Ok(layout)
+    }
+
+    pub fn add_stage(&mut self, words: &[u32]) -> Result<()> {
+        let module = spirv::parse(words)?;
+        let visibility = visibility(module.stage);
+
+        for variable in module.resources() {
+            let set = variable.set.unwrap_or(0);
+            let binding = variable.binding.unwrap_or(0);
+            let (ty, count) = binding_type(variable)
+                .with_context(|| format!("set={}, binding={}", set, binding))?;
+
+            let entries = self.sets.entry(set).or_default();
+            match entries.get_mut(&binding) {
+                Some(entry) => {
+                    ensure!(
+                        entry.ty == ty && entry.count == count,
+                        "set={}, binding={} is declared as {:?} and {:?}",
+                        set,
+                        binding,
+                        entry.ty,
+                        ty
+                    );
+                    entry.visibility |= visibility;
+                }
+                None => {
+                    entries.insert(
+                        binding,
+                        wgpu::BindGroupLayoutEntry {
+                            binding,
+                            visibility,
+                            ty,
+                            count,
+                        },
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn entries(&self, set: u32) -> Vec<wgpu::BindGroupLayoutEntry> {
+        self.sets
+            .get(&set)
+            .map(|entries| entries.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn create_bind_group_layout(
+        &self,
+        device: &wgpu::Device,
+        set: u32,
+        label: &str,
+    ) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &self.entries(set),
+            label: Some(label),
+        })
+    }
+}
@@ -1,9 +1,20 @@
 #version 450
 
+#ifndef NORMAL_MAP
+#define NORMAL_MAP 1
+#endif
+#ifndef SPECULAR
+#define SPECULAR 1
+#endif
+#ifndef DEBUG_NORMALS
+#define DEBUG_NORMALS 0
+#endif
+
 layout(location=0) in vec2 v_tex_coords;
 layout(location=1) in vec3 v_position;
 layout(location=2) in vec3 v_light_position;
 layout(location=3) in vec3 v_view_position;
+layout(location=4) flat in float v_selected;
 
 layout(location=0) out vec4 f_color;
 
@@ -12,29 +23,43 @@ layout(set = 0, binding = 1) uniform sampler s_diffuse;
 layout(set = 0, binding = 2) uniform texture2D t_normal;
 layout(set = 0, binding = 3) uniform sampler s_normal;
 
-layout(set = 2, binding = 0) uniform Light {
-    vec3 light_position;
-    vec3 light_color;
-};
+#define LIGHT_SET 2
+#include "common/uniforms.glsl"
 
 void main() {
     vec4 object_color = texture(sampler2D(t_diffuse, s_diffuse), v_tex_coords);
-    vec4 object_normal = texture(sampler2D(t_normal, s_normal), v_tex_coords);
 
     float ambient_strength = 0.1;
     vec3 ambient_color = light_color * ambient_strength;
 
+#if NORMAL_MAP
+    vec4 object_normal = texture(sampler2D(t_normal, s_normal), v_tex_coords);
     vec3 normal = normalize(object_normal.rgb * 2.0 - 1.0);
+#else
+    // Lighting happens in tangent space, where the unperturbed normal is +Z
+    vec3 normal = vec3(0.0, 0.0, 1.0);
+#endif
+
+#if DEBUG_NORMALS
+    f_color = vec4(normal * 0.5 + 0.5, 1.0);
+    return;
+#endif
+
     vec3 light_dir = normalize(v_light_position - v_position);
     
     float diffuse_strength = max(dot(normal, light_dir), 0.0);
     vec3 diffuse_color = light_color * diffuse_strength;
 
+#if SPECULAR
     vec3 view_dir = normalize(v_view_position - v_position);
     vec3 half_dir = normalize(view_dir + light_dir);
     float specular_strength = pow(max(dot(normal, half_dir), 0.0), 32);
     vec3 specular_color = specular_strength * light_color;
+#else
+    vec3 specular_color = vec3(0.0);
+#endif
 
     vec3 result = (ambient_color + diffuse_color + specular_color) * object_color.xyz;
+    result = mix(result, vec3(1.0, 0.6, 0.1), 0.5 * v_selected);
     f_color = vec4(result, object_color.a);
 }
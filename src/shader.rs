@@ -0,0 +1,142 @@
+use anyhow::*;
+use std::borrow::Cow;
+use std::fs::{metadata, read_to_string};
+use std::iter;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::{include, wgsl};
+
+/// Directory the GLSL sources live in, so they can be found while the app runs.
+pub const SHADER_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src");
+
+fn shader_kind(path: &Path) -> Result<shaderc::ShaderKind> {
+    let extension = path
+        .extension()
+        .context("File has no extension")?
+        .to_str()
+        .context("Extension cannot be converted to &str")?;
+    Ok(match extension {
+        "vert" => shaderc::ShaderKind::Vertex,
+        "frag" => shaderc::ShaderKind::Fragment,
+        "comp" => shaderc::ShaderKind::Compute,
+        _ => bail!("Unsupported shader: {}", path.display()),
+    })
+}
+
+fn load_wgsl(path: &Path) -> Result<wgpu::ShaderModuleSource<'static>> {
+    let src = read_to_string(path)?;
+    wgsl::validate(path, &src)?;
+    Ok(wgpu::ShaderModuleSource::Wgsl(Cow::Owned(src)))
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Modification times of a shader source and everything it includes.
+fn snapshot(path: &Path) -> Vec<(PathBuf, Option<SystemTime>)> {
+    let includes = read_to_string(path)
+        .ok()
+        .and_then(|src| include::dependencies(Path::new(SHADER_DIR), &src).ok())
+        .unwrap_or_default();
+
+    iter::once(path.to_path_buf())
+        .chain(includes)
+        .map(|file| {
+            let modified = modified(&file);
+            (file, modified)
+        })
+        .collect()
+}
+
+struct WatchedShader {
+    path: PathBuf,
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+/// Watches GLSL and WGSL sources on disk and recompiles them when they change.
+pub struct ShaderWatcher {
+    compiler: shaderc::Compiler,
+    shaders: Vec<WatchedShader>,
+}
+
+impl ShaderWatcher {
+    pub fn new<P: AsRef<Path>>(names: &[P]) -> Result<Self> {
+        let compiler = shaderc::Compiler::new().context("Unable to create shader compiler")?;
+        let shaders = names
+            .iter()
+            .map(|name| {
+                let path = Path::new(SHADER_DIR).join(name);
+                let files = snapshot(&path);
+                WatchedShader { path, files }
+            })
+            .collect();
+
+        Ok(Self { compiler, shaders })
+    }
+
+    /// Returns the file names of the sources modified since the last poll,
+    /// either directly or through one of their includes.
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        for shader in &mut self.shaders {
+            let stale = shader
+                .files
+                .iter()
+                .any(|(file, last_modified)| modified(file) != *last_modified);
+            if stale {
+                shader.files = snapshot(&shader.path);
+                if let Some(name) = shader.path.file_name() {
+                    changed.push(PathBuf::from(name));
+                }
+            }
+        }
+        changed
+    }
+
+    pub fn compile<P: AsRef<Path>>(
+        &mut self,
+        name: P,
+        defines: &[(&str, Option<&str>)],
+    ) -> Result<wgpu::ShaderModuleSource<'static>> {
+        let path = Path::new(SHADER_DIR).join(name);
+        if path.extension() == Some("wgsl".as_ref()) {
+            ensure!(defines.is_empty(), "WGSL shaders don't support defines");
+            return load_wgsl(&path);
+        }
+        let kind = shader_kind(&path)?;
+        let src = read_to_string(&path)?;
+        self.compile_source(&src, kind, &path.to_string_lossy(), defines)
+    }
+
+    /// Compiles GLSL that was put together in memory rather than read from a
+    /// single file. `file_name` is what errors are reported against.
+    pub fn compile_source(
+        &mut self,
+        src: &str,
+        kind: shaderc::ShaderKind,
+        file_name: &str,
+        defines: &[(&str, Option<&str>)],
+    ) -> Result<wgpu::ShaderModuleSource<'static>> {
+        let mut options =
+            shaderc::CompileOptions::new().context("Unable to create compile options")?;
+        options.set_include_callback(|requested, _, _, _| {
+            include::resolve(Path::new(SHADER_DIR), requested)
+        });
+        for (name, value) in defines {
+            options.add_macro_definition(name, *value);
+        }
+
+        let compiled =
+            self.compiler
+                .compile_into_spirv(src, kind, file_name, "main", Some(&options))?;
+        if compiled.get_num_warnings() > 0 {
+            log::warn!("{}", compiled.get_warning_messages());
+        }
+
+        Ok(wgpu::ShaderModuleSource::SpirV(Cow::Owned(
+            compiled.as_binary().to_vec(),
+        )))
+    }
+}
@@ -10,25 +10,20 @@ layout(location=0) out vec2 v_tex_coords;
 layout(location=1) out vec3 v_position;
 layout(location=2) out vec3 v_light_position;
 layout(location=3) out vec3 v_view_position;
+layout(location=4) flat out float v_selected;
 
-layout(set=1, binding=0) 
-uniform Uniforms {
-    vec3 u_view_position; 
-    mat4 u_view_proj;
-};
+#define UNIFORMS_SET 1
+#define LIGHT_SET 2
+#include "common/uniforms.glsl"
 
 layout(set=1, binding=1) 
-buffer Instances {
+readonly buffer Instances {
     mat4 s_models[];
 };
 
-layout(set=2, binding = 0) uniform Light {
-    vec3 light_position;
-    vec3 light_color;
-};
-
 void main() {
     v_tex_coords = a_tex_coords;
+    v_selected = gl_InstanceIndex == u_selected_instance ? 1.0 : 0.0;
 
     mat4 model_matrix = s_models[gl_InstanceIndex];
 
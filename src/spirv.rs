@@ -0,0 +1,625 @@
+//! Minimal SPIR-V reader shared by `build.rs` and the runtime. It only
+//! understands what is needed to describe a shader's interface: the stage,
+//! the resources it binds and its input/output variables.
+// Each side only uses part of the reader.
+#![allow(dead_code)]
+
+use anyhow::*;
+use std::collections::HashMap;
+
+const MAGIC_NUMBER: u32 = 0x0723_0203;
+
+mod op {
+    pub const NAME: u32 = 5;
+    pub const MEMBER_NAME: u32 = 6;
+    pub const ENTRY_POINT: u32 = 15;
+    pub const TYPE_BOOL: u32 = 20;
+    pub const TYPE_INT: u32 = 21;
+    pub const TYPE_FLOAT: u32 = 22;
+    pub const TYPE_VECTOR: u32 = 23;
+    pub const TYPE_MATRIX: u32 = 24;
+    pub const TYPE_IMAGE: u32 = 25;
+    pub const TYPE_SAMPLER: u32 = 26;
+    pub const TYPE_SAMPLED_IMAGE: u32 = 27;
+    pub const TYPE_ARRAY: u32 = 28;
+    pub const TYPE_RUNTIME_ARRAY: u32 = 29;
+    pub const TYPE_STRUCT: u32 = 30;
+    pub const TYPE_POINTER: u32 = 32;
+    pub const CONSTANT: u32 = 43;
+    pub const VARIABLE: u32 = 59;
+    pub const DECORATE: u32 = 71;
+    pub const MEMBER_DECORATE: u32 = 72;
+}
+
+mod decoration {
+    pub const BLOCK: u32 = 2;
+    pub const BUFFER_BLOCK: u32 = 3;
+    pub const ROW_MAJOR: u32 = 4;
+    pub const ARRAY_STRIDE: u32 = 6;
+    pub const MATRIX_STRIDE: u32 = 7;
+    pub const BUILT_IN: u32 = 11;
+    pub const NON_WRITABLE: u32 = 24;
+    pub const LOCATION: u32 = 30;
+    pub const BINDING: u32 = 33;
+    pub const DESCRIPTOR_SET: u32 = 34;
+    pub const OFFSET: u32 = 35;
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Stage {
+    Vertex,
+    Fragment,
+    Compute,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StorageClass {
+    UniformConstant,
+    Input,
+    Uniform,
+    Output,
+    PushConstant,
+    StorageBuffer,
+    Other(u32),
+}
+
+impl From<u32> for StorageClass {
+    fn from(x: u32) -> Self {
+        match x {
+            0 => Self::UniformConstant,
+            1 => Self::Input,
+            2 => Self::Uniform,
+            3 => Self::Output,
+            9 => Self::PushConstant,
+            12 => Self::StorageBuffer,
+            x => Self::Other(x),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ImageDim {
+    D1,
+    D2,
+    D3,
+    Cube,
+    Other(u32),
+}
+
+impl From<u32> for ImageDim {
+    fn from(x: u32) -> Self {
+        match x {
+            0 => Self::D1,
+            1 => Self::D2,
+            2 => Self::D3,
+            3 => Self::Cube,
+            x => Self::Other(x),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Member {
+    pub name: String,
+    pub offset: Option<u32>,
+    pub matrix_stride: Option<u32>,
+    pub row_major: bool,
+    pub built_in: bool,
+    pub non_writable: bool,
+    pub ty: Type,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Bool,
+    Int {
+        width: u32,
+        signed: bool,
+    },
+    Float {
+        width: u32,
+    },
+    Vector {
+        component: Box<Type>,
+        count: u32,
+    },
+    Matrix {
+        column: Box<Type>,
+        columns: u32,
+    },
+    Image {
+        sampled_type: Box<Type>,
+        dim: ImageDim,
+        depth: bool,
+        arrayed: bool,
+        multisampled: bool,
+        /// Read/written as a storage image rather than sampled.
+        storage: bool,
+        /// Raw SPIR-V `ImageFormat`, only meaningful for storage images.
+        format: u32,
+    },
+    Sampler,
+    SampledImage(Box<Type>),
+    Array {
+        element: Box<Type>,
+        length: u32,
+        stride: Option<u32>,
+    },
+    RuntimeArray {
+        element: Box<Type>,
+        stride: Option<u32>,
+    },
+    Struct {
+        name: String,
+        members: Vec<Member>,
+        block: bool,
+        buffer_block: bool,
+    },
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variable {
+    pub name: String,
+    pub storage: StorageClass,
+    pub ty: Type,
+    pub set: Option<u32>,
+    pub binding: Option<u32>,
+    pub location: Option<u32>,
+    pub built_in: bool,
+    pub non_writable: bool,
+}
+
+impl Variable {
+    /// Storage buffers show up either in their own storage class or, from
+    /// older GLSL targets, as `Uniform` blocks decorated `BufferBlock`.
+    pub fn is_storage_buffer(&self) -> bool {
+        match (&self.storage, &self.ty) {
+            (StorageClass::StorageBuffer, _) => true,
+            (StorageClass::Uniform, Type::Struct { buffer_block, .. }) => *buffer_block,
+            _ => false,
+        }
+    }
+
+    pub fn is_uniform_buffer(&self) -> bool {
+        self.storage == StorageClass::Uniform && !self.is_storage_buffer()
+    }
+
+    /// Built-ins such as `gl_Position` are either decorated directly or
+    /// wrapped in a block whose members are.
+    pub fn is_built_in(&self) -> bool {
+        self.built_in
+            || match &self.ty {
+                Type::Struct { members, .. } => members.iter().any(|m| m.built_in),
+                _ => false,
+            }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Module {
+    pub stage: Stage,
+    pub entry_point: String,
+    /// Resources and the variables listed in the entry point's interface.
+    pub variables: Vec<Variable>,
+}
+
+impl Module {
+    pub fn resources(&self) -> impl Iterator<Item = &Variable> {
+        self.variables.iter().filter(|v| v.binding.is_some())
+    }
+
+    pub fn inputs(&self) -> impl Iterator<Item = &Variable> {
+        self.variables
+            .iter()
+            .filter(|v| v.storage == StorageClass::Input && !v.is_built_in())
+    }
+
+    pub fn outputs(&self) -> impl Iterator<Item = &Variable> {
+        self.variables
+            .iter()
+            .filter(|v| v.storage == StorageClass::Output && !v.is_built_in())
+    }
+}
+
+fn read_string(words: &[u32]) -> (String, usize) {
+    let mut bytes = Vec::new();
+    for (i, word) in words.iter().enumerate() {
+        for byte in word.to_le_bytes().iter() {
+            if *byte == 0 {
+                return (String::from_utf8_lossy(&bytes).into_owned(), i + 1);
+            }
+            bytes.push(*byte);
+        }
+    }
+    (String::from_utf8_lossy(&bytes).into_owned(), words.len())
+}
+
+#[derive(Default)]
+struct Decorations {
+    flags: Vec<u32>,
+    values: HashMap<u32, u32>,
+}
+
+impl Decorations {
+    fn add(&mut self, operands: &[u32]) {
+        match operands {
+            [decoration] => self.flags.push(*decoration),
+            [decoration, value, ..] => {
+                self.flags.push(*decoration);
+                self.values.insert(*decoration, *value);
+            }
+            [] => {}
+        }
+    }
+
+    fn has(&self, decoration: u32) -> bool {
+        self.flags.contains(&decoration)
+    }
+
+    fn get(&self, decoration: u32) -> Option<u32> {
+        self.values.get(&decoration).copied()
+    }
+}
+
+#[derive(Default)]
+struct Parser<'a> {
+    names: HashMap<u32, String>,
+    member_names: HashMap<(u32, u32), String>,
+    decorations: HashMap<u32, Decorations>,
+    member_decorations: HashMap<(u32, u32), Decorations>,
+    types: HashMap<u32, (u32, &'a [u32])>,
+    constants: HashMap<u32, u32>,
+}
+
+impl<'a> Parser<'a> {
+    fn decorations(&self, id: u32) -> Option<&Decorations> {
+        self.decorations.get(&id)
+    }
+
+    fn resolve(&self, id: u32) -> Result<Type> {
+        let (opcode, operands) = *self
+            .types
+            .get(&id)
+            .with_context(|| format!("Unknown SPIR-V type %{}", id))?;
+        let operand = |i: usize| {
+            operands
+                .get(i)
+                .copied()
+                .with_context(|| format!("Truncated SPIR-V type %{}", id))
+        };
+        let stride = self
+            .decorations(id)
+            .and_then(|d| d.get(decoration::ARRAY_STRIDE));
+
+        Ok(match opcode {
+            op::TYPE_BOOL => Type::Bool,
+            op::TYPE_INT => Type::Int {
+                width: operand(0)?,
+                signed: operand(1)? != 0,
+            },
+            op::TYPE_FLOAT => Type::Float { width: operand(0)? },
+            op::TYPE_VECTOR => Type::Vector {
+                component: Box::new(self.resolve(operand(0)?)?),
+                count: operand(1)?,
+            },
+            op::TYPE_MATRIX => Type::Matrix {
+                column: Box::new(self.resolve(operand(0)?)?),
+                columns: operand(1)?,
+            },
+            op::TYPE_IMAGE => Type::Image {
+                sampled_type: Box::new(self.resolve(operand(0)?)?),
+                dim: operand(1)?.into(),
+                depth: operand(2)? == 1,
+                arrayed: operand(3)? != 0,
+                multisampled: operand(4)? != 0,
+                storage: operand(5)? == 2,
+                format: operand(6)?,
+            },
+            op::TYPE_SAMPLER => Type::Sampler,
+            op::TYPE_SAMPLED_IMAGE => Type::SampledImage(Box::new(self.resolve(operand(0)?)?)),
+            op::TYPE_ARRAY => Type::Array {
+                element: Box::new(self.resolve(operand(0)?)?),
+                length: *self
+                    .constants
+                    .get(&operand(1)?)
+                    .context("Array length is not a constant")?,
+                stride,
+            },
+            op::TYPE_RUNTIME_ARRAY => Type::RuntimeArray {
+                element: Box::new(self.resolve(operand(0)?)?),
+                stride,
+            },
+            op::TYPE_STRUCT => {
+                let members = operands
+                    .iter()
+                    .enumerate()
+                    .map(|(i, member)| {
+                        let key = (id, i as u32);
+                        let decorations = self.member_decorations.get(&key);
+                        let flag = |d| decorations.is_some_and(|m| m.has(d));
+                        let value = |d| decorations.and_then(|m| m.get(d));
+                        Ok(Member {
+                            name: self.member_names.get(&key).cloned().unwrap_or_default(),
+                            offset: value(decoration::OFFSET),
+                            matrix_stride: value(decoration::MATRIX_STRIDE),
+                            row_major: flag(decoration::ROW_MAJOR),
+                            built_in: flag(decoration::BUILT_IN),
+                            non_writable: flag(decoration::NON_WRITABLE),
+                            ty: self.resolve(*member)?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let flag = |d| self.decorations(id).is_some_and(|m| m.has(d));
+                Type::Struct {
+                    name: self.names.get(&id).cloned().unwrap_or_default(),
+                    members,
+                    block: flag(decoration::BLOCK),
+                    buffer_block: flag(decoration::BUFFER_BLOCK),
+                }
+            }
+            _ => Type::Other,
+        })
+    }
+}
+
+pub fn parse(words: &[u32]) -> Result<Module> {
+    ensure!(
+        words.len() >= 5 && words[0] == MAGIC_NUMBER,
+        "Not a SPIR-V module"
+    );
+
+    let mut parser = Parser::default();
+    let mut entry_point = None;
+    let mut variables = Vec::new();
+
+    let mut rest = &words[5..];
+    while !rest.is_empty() {
+        let word_count = (rest[0] >> 16) as usize;
+        let opcode = rest[0] & 0xffff;
+        ensure!(
+            word_count > 0 && word_count <= rest.len(),
+            "Malformed SPIR-V instruction"
+        );
+        let operands = &rest[1..word_count];
+        rest = &rest[word_count..];
+
+        match opcode {
+            op::NAME if !operands.is_empty() => {
+                let (name, _) = read_string(&operands[1..]);
+                parser.names.insert(operands[0], name);
+            }
+            op::MEMBER_NAME if operands.len() > 1 => {
+                let (name, _) = read_string(&operands[2..]);
+                parser.member_names.insert((operands[0], operands[1]), name);
+            }
+            op::ENTRY_POINT if entry_point.is_none() && operands.len() > 2 => {
+                let stage = match operands[0] {
+                    0 => Stage::Vertex,
+                    4 => Stage::Fragment,
+                    5 => Stage::Compute,
+                    model => bail!("Unsupported execution model {}", model),
+                };
+                let (name, len) = read_string(&operands[2..]);
+                let interface = operands[2 + len..].to_vec();
+                entry_point = Some((stage, name, interface));
+            }
+            op::TYPE_BOOL..=op::TYPE_STRUCT if !operands.is_empty() => {
+                parser.types.insert(operands[0], (opcode, &operands[1..]));
+            }
+            op::TYPE_POINTER if operands.len() == 3 => {
+                parser.types.insert(operands[0], (opcode, &operands[1..]));
+            }
+            op::CONSTANT if operands.len() > 2 => {
+                parser.constants.insert(operands[1], operands[2]);
+            }
+            op::VARIABLE if operands.len() > 2 => {
+                variables.push((operands[0], operands[1], operands[2]));
+            }
+            op::DECORATE if !operands.is_empty() => {
+                parser
+                    .decorations
+                    .entry(operands[0])
+                    .or_default()
+                    .add(&operands[1..]);
+            }
+            op::MEMBER_DECORATE if operands.len() > 1 => {
+                parser
+                    .member_decorations
+                    .entry((operands[0], operands[1]))
+                    .or_default()
+                    .add(&operands[2..]);
+            }
+            _ => {}
+        }
+    }
+
+    let (stage, entry_point, interface) = entry_point.context("SPIR-V has no entry point")?;
+
+    let variables = variables
+        .into_iter()
+        .filter_map(|(pointer, id, storage)| {
+            let storage = StorageClass::from(storage);
+            let in_interface = interface.contains(&id);
+            let is_resource = parser
+                .decorations(id)
+                .is_some_and(|d| d.has(decoration::BINDING));
+            match storage {
+                StorageClass::Input | StorageClass::Output if in_interface => {}
+                _ if is_resource => {}
+                StorageClass::PushConstant => {}
+                _ => return None,
+            }
+            Some((pointer, id, storage))
+        })
+        .map(|(pointer, id, storage)| {
+            let pointee = match parser.types.get(&pointer) {
+                Some((op::TYPE_POINTER, operands)) => operands[1],
+                _ => bail!("Variable %{} is not a pointer", id),
+            };
+            let decorations = parser.decorations(id);
+            let flag = |d| decorations.is_some_and(|m| m.has(d));
+            let value = |d| decorations.and_then(|m| m.get(d));
+            let ty = parser.resolve(pointee)?;
+            let non_writable = flag(decoration::NON_WRITABLE)
+                || match &ty {
+                    Type::Struct { members, .. } => {
+                        !members.is_empty() && members.iter().all(|m| m.non_writable)
+                    }
+                    _ => false,
+                };
+
+            Ok(Variable {
+                name: parser.names.get(&id).cloned().unwrap_or_default(),
+                storage,
+                ty,
+                set: value(decoration::DESCRIPTOR_SET),
+                binding: value(decoration::BINDING),
+                location: value(decoration::LOCATION),
+                built_in: flag(decoration::BUILT_IN),
+                non_writable,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Module {
+        stage,
+        entry_point,
+        variables,
+    })
+}
+
+/// Reinterprets SPIR-V bytes, e.g. from a `.sprv` file, as words.
+pub fn words(bytes: &[u8]) -> Result<Vec<u32>> {
+    ensure!(
+        bytes.len().is_multiple_of(4),
+        "SPIR-V size is not a multiple of 4"
+    );
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instruction(opcode: u32, operands: &[u32]) -> Vec<u32> {
+        let mut words = vec![((operands.len() as u32 + 1) << 16) | opcode];
+        words.extend_from_slice(operands);
+        words
+    }
+
+    /// A nul terminated string padded to whole words.
+    fn string(s: &str) -> Vec<u32> {
+        let mut bytes = s.as_bytes().to_vec();
+        bytes.resize(4 * (s.len() / 4 + 1), 0);
+        words(&bytes).unwrap()
+    }
+
+    /// A fragment shader writing `out_color` at location 0 and reading a
+    /// uniform block `u` at set 1, binding 2.
+    fn fragment_shader() -> Vec<u32> {
+        let mut module = vec![MAGIC_NUMBER, 0x0001_0000, 0, 20, 0];
+        let mut push = |opcode, operands: &[u32]| module.extend(instruction(opcode, operands));
+        push(
+            op::ENTRY_POINT,
+            &[&[4, 1][..], &string("main"), &[10]].concat(),
+        );
+        push(op::NAME, &[&[10][..], &string("out_color")].concat());
+        push(op::NAME, &[&[11][..], &string("u")].concat());
+        push(op::MEMBER_NAME, &[&[5, 0][..], &string("color")].concat());
+        push(op::DECORATE, &[10, decoration::LOCATION, 0]);
+        push(op::DECORATE, &[5, decoration::BLOCK]);
+        push(op::MEMBER_DECORATE, &[5, 0, decoration::OFFSET, 0]);
+        push(op::DECORATE, &[11, decoration::DESCRIPTOR_SET, 1]);
+        push(op::DECORATE, &[11, decoration::BINDING, 2]);
+        push(op::TYPE_FLOAT, &[2, 32]);
+        push(op::TYPE_VECTOR, &[3, 2, 4]);
+        push(op::TYPE_POINTER, &[4, 3, 3]);
+        push(op::TYPE_STRUCT, &[5, 3]);
+        push(op::TYPE_POINTER, &[6, 2, 5]);
+        push(op::VARIABLE, &[4, 10, 3]);
+        push(op::VARIABLE, &[6, 11, 2]);
+        module
+    }
+
+    #[test]
+    fn reads_the_interface() {
+        let module = parse(&fragment_shader()).unwrap();
+        assert_eq!(module.stage, Stage::Fragment);
+        assert_eq!(module.entry_point, "main");
+
+        let vec4 = Type::Vector {
+            component: Box::new(Type::Float { width: 32 }),
+            count: 4,
+        };
+        let outputs = module.outputs().collect::<Vec<_>>();
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0].name, "out_color");
+        assert_eq!(outputs[0].location, Some(0));
+        assert_eq!(outputs[0].ty, vec4);
+        assert_eq!(module.inputs().count(), 0);
+
+        let resources = module.resources().collect::<Vec<_>>();
+        assert_eq!(resources.len(), 1);
+        let uniform = resources[0];
+        assert_eq!(uniform.name, "u");
+        assert_eq!((uniform.set, uniform.binding), (Some(1), Some(2)));
+        assert!(uniform.is_uniform_buffer());
+        match &uniform.ty {
+            Type::Struct { members, block, .. } => {
+                assert!(*block);
+                assert_eq!(members.len(), 1);
+                assert_eq!(members[0].name, "color");
+                assert_eq!(members[0].offset, Some(0));
+                assert_eq!(members[0].ty, vec4);
+            }
+            ty => panic!("{:?} is not a struct", ty),
+        }
+    }
+
+    #[test]
+    fn rejects_a_bad_magic_number() {
+        let mut module = fragment_shader();
+        module[0] = MAGIC_NUMBER.swap_bytes();
+        assert!(parse(&module).is_err());
+    }
+
+    #[test]
+    fn rejects_a_truncated_header() {
+        assert!(parse(&fragment_shader()[..4]).is_err());
+        assert!(parse(&[]).is_err());
+    }
+
+    #[test]
+    fn rejects_a_truncated_instruction() {
+        let module = fragment_shader();
+        assert!(parse(&module[..module.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn rejects_an_empty_instruction() {
+        let mut module = fragment_shader();
+        module.push(0);
+        assert!(parse(&module).is_err());
+    }
+
+    #[test]
+    fn rejects_a_module_without_an_entry_point() {
+        let module = fragment_shader();
+        let entry_point_len = (module[5] >> 16) as usize;
+        let without = [&module[..5], &module[5 + entry_point_len..]].concat();
+        assert!(parse(&without).is_err());
+    }
+
+    #[test]
+    fn words_are_little_endian_and_whole() {
+        assert_eq!(
+            words(&[0x03, 0x02, 0x23, 0x07]).unwrap(),
+            vec![MAGIC_NUMBER]
+        );
+        assert!(words(&[0x03, 0x02, 0x23]).is_err());
+    }
+}
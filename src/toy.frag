@@ -0,0 +1,36 @@
+#version 450
+
+// Prelude for Shadertoy style shaders. At runtime the toy's source is appended
+// to this file with TOY_SOURCE defined, plus TOY_BUFFER for Buffer A-D. The
+// build compiles it on its own with a placeholder `mainImage`, which is where
+// the uniform struct and bind group layouts come from.
+
+layout(location=0) out vec4 f_color;
+
+#include "common/toy.glsl"
+
+void mainImage(out vec4 fragColor, in vec2 fragCoord);
+
+void main() {
+    vec4 color = vec4(0.0, 0.0, 0.0, 1.0);
+#ifdef TOY_BUFFER
+    // Buffers are stored bottom row first, like Shadertoy's, so sampling
+    // them at fragCoord / iResolution.xy lines up without a flip
+    mainImage(color, gl_FragCoord.xy);
+    f_color = color;
+#else
+    // Shadertoy puts the origin in the bottom left corner
+    mainImage(color, vec2(gl_FragCoord.x, iResolution.y - gl_FragCoord.y));
+    f_color = vec4(color.rgb, 1.0);
+#endif
+}
+
+#ifndef TOY_SOURCE
+// Reads every channel, so none of them is optimized out of the reflection data
+void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+    vec2 uv = fragCoord / iResolution.xy;
+    vec4 channels = texture(iChannel0, uv) + texture(iChannel1, uv)
+        + texture(iChannel2, uv) + texture(iChannel3, uv);
+    fragColor = vec4(0.5 + 0.5 * cos(iTime + uv.xyx + vec3(0.0, 2.0, 4.0)), 1.0) + channels;
+}
+#endif
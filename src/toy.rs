@@ -0,0 +1,511 @@
+//! Shadertoy style playground: runs a `mainImage(out vec4, in vec2)` from a
+//! GLSL file over the whole window.
+//!
+//! Next to `toy.glsl`, the files `toy.a.glsl` to `toy.d.glsl` are picked up
+//! as Buffer A-D. Each is drawn offscreen every frame, in order, before the
+//! image. A buffer drawn earlier in the frame reads as this frame's output,
+//! the buffer itself and later ones as last frame's. What each pass reads
+//! through `iChannel0..3` is set up in `channels.rs`.
+
+use anyhow::*;
+use chrono::{Datelike, Timelike};
+use std::fs::read_to_string;
+use std::iter;
+use std::path::{Path, PathBuf};
+use wgpu::util::DeviceExt;
+use winit::{dpi::PhysicalPosition, event::*, window::Window};
+
+use crate::channels::{self, Channel, BUFFER_NAMES, CHANNELS};
+use crate::{reflect, shader, texture, ToyUniforms, Vec3, Vec4};
+
+/// Shadertoy keeps its buffers in floating point too.
+const BUFFER_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+/// The full-screen triangle every pass is drawn with.
+const TOY_VERTEX: &str = "toy.vert.wgsl";
+
+/// `toy.glsl` becomes `toy.a.glsl` for Buffer A.
+fn buffer_path(path: &Path, name: &str) -> PathBuf {
+    let extension = path.extension().unwrap_or_default().to_string_lossy();
+    path.with_extension(format!("{}.{}", name, extension))
+}
+
+/// Wraps the toy's source in the `toy.frag` prelude and compiles it.
+fn compile_toy(
+    shader_watcher: &mut shader::ShaderWatcher,
+    path: &Path,
+    is_buffer: bool,
+) -> Result<wgpu::ShaderModuleSource<'static>> {
+    let prelude = read_to_string(Path::new(shader::SHADER_DIR).join("toy.frag"))?;
+    let src = read_to_string(path)?;
+    // Restart the line count so errors point into the toy's own file
+    let src = format!("{}\n#line 1\n{}", prelude, src);
+    let mut defines = vec![("TOY_SOURCE", None)];
+    if is_buffer {
+        defines.push(("TOY_BUFFER", None));
+    }
+    shader_watcher.compile_source(
+        &src,
+        shaderc::ShaderKind::Fragment,
+        &path.to_string_lossy(),
+        &defines,
+    )
+}
+
+/// Compiles the pass at `path` along with the WGSL vertex shader.
+fn create_toy_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    color_format: wgpu::TextureFormat,
+    shader_watcher: &mut shader::ShaderWatcher,
+    path: &Path,
+    is_buffer: bool,
+) -> Result<wgpu::RenderPipeline> {
+    let fs_src = compile_toy(shader_watcher, path, is_buffer)?;
+    let vs_src = shader_watcher.compile(TOY_VERTEX, &[])?;
+    Ok(crate::create_render_pipeline(
+        device,
+        layout,
+        color_format,
+        None,
+        &[],
+        vs_src,
+        fs_src,
+    ))
+}
+
+/// Draws a full-screen pass into `target`.
+fn draw_pass(
+    encoder: &mut wgpu::CommandEncoder,
+    target: &wgpu::TextureView,
+    pipeline: &wgpu::RenderPipeline,
+    uniform_bind_group: &wgpu::BindGroup,
+    channel_bind_group: &wgpu::BindGroup,
+) {
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+            attachment: target,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                store: true,
+            },
+        }],
+        depth_stencil_attachment: None,
+    });
+    render_pass.set_pipeline(pipeline);
+    render_pass.set_bind_group(0, uniform_bind_group, &[]);
+    render_pass.set_bind_group(1, channel_bind_group, &[]);
+    render_pass.draw(0..3, 0..1);
+}
+
+/// One of Buffer A-D. It renders into one of its two targets while the other
+/// still holds its previous frame.
+struct Buffer {
+    path: PathBuf,
+    pipeline: wgpu::RenderPipeline,
+    targets: [texture::Texture; 2],
+    /// The target holding the most recently finished frame.
+    current: usize,
+    channels: [Channel; CHANNELS],
+}
+
+impl Buffer {
+    fn create_targets(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        sc_desc: &wgpu::SwapChainDescriptor,
+    ) -> [texture::Texture; 2] {
+        let create = |label| {
+            texture::Texture::create_render_target(
+                device,
+                sc_desc.width,
+                sc_desc.height,
+                BUFFER_FORMAT,
+                label,
+            )
+        };
+        let targets = [create("toy_buffer_0"), create("toy_buffer_1")];
+
+        // Shaders that accumulate expect to start from black
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Toy Buffer Clear Encoder"),
+        });
+        for target in &targets {
+            encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                    attachment: &target.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: None,
+            });
+        }
+        queue.submit(iter::once(encoder.finish()));
+
+        targets
+    }
+}
+
+/// `iDate`: year, month (from 0), day (from 1) and seconds since midnight.
+fn date() -> Vec4 {
+    let now = chrono::Local::now();
+    let seconds =
+        now.num_seconds_from_midnight() as f32 + now.nanosecond() as f32 / 1_000_000_000.0;
+    Vec4::new(
+        now.year() as f32,
+        now.month0() as f32,
+        now.day() as f32,
+        seconds,
+    )
+}
+
+pub struct Toy {
+    surface: wgpu::Surface,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    sc_desc: wgpu::SwapChainDescriptor,
+    swap_chain: wgpu::SwapChain,
+    path: PathBuf,
+    pipeline: wgpu::RenderPipeline,
+    pipeline_layout: wgpu::PipelineLayout,
+    uniforms: ToyUniforms,
+    uniform_buffer: wgpu::Buffer,
+    uniform_bind_group: wgpu::BindGroup,
+    channel_bind_group_layout: wgpu::BindGroupLayout,
+    channel_sampler: wgpu::Sampler,
+    /// Bound to channels that have nothing to read.
+    empty_channel: texture::Texture,
+    buffers: [Option<Buffer>; CHANNELS],
+    /// What the image pass reads.
+    channels: [Channel; CHANNELS],
+    inputs: channels::Inputs,
+    shader_watcher: shader::ShaderWatcher,
+    mouse_pos: PhysicalPosition<f64>,
+    mouse_pressed: bool,
+}
+
+impl Toy {
+    pub async fn new(window: &Window, path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let size = window.inner_size();
+        let (surface, device, queue) = crate::request_device(window).await?;
+
+        // Shadertoy writes its colors to the screen as they are, without an
+        // sRGB conversion
+        let sc_desc = wgpu::SwapChainDescriptor {
+            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+            format: wgpu::TextureFormat::Bgra8Unorm,
+            width: size.width,
+            height: size.height,
+            present_mode: wgpu::PresentMode::Fifo,
+        };
+        let swap_chain = device.create_swap_chain(&surface, &sc_desc);
+
+        let layout = reflect::Layout::from_spirv(&[&include_bytes!("toy.frag.sprv")[..]])?;
+        let uniform_bind_group_layout =
+            layout.create_bind_group_layout(&device, 0, "toy_bind_group_layout");
+
+        let uniforms = ToyUniforms::new(
+            Vec3::new(size.width as f32, size.height as f32, 1.0),
+            0.0,
+            Vec4::zero(),
+            date(),
+            0.0,
+            0,
+        );
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Toy Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[uniforms]),
+            usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+        });
+        let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &uniform_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(uniform_buffer.slice(..)),
+            }],
+            label: Some("toy_bind_group"),
+        });
+
+        let channel_bind_group_layout =
+            layout.create_bind_group_layout(&device, 1, "toy_channel_bind_group_layout");
+        let channel_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+        let empty_channel = texture::Texture::from_image(
+            &device,
+            &queue,
+            &image::DynamicImage::new_rgba8(1, 1),
+            Some("empty_channel"),
+            false,
+        )?;
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Toy Pipeline Layout"),
+            bind_group_layouts: &[&uniform_bind_group_layout, &channel_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        // Watched by absolute path, so it can live outside of the shader directory
+        let path = std::fs::canonicalize(path)?;
+        let buffer_paths = BUFFER_NAMES
+            .iter()
+            .map(|name| Some(buffer_path(&path, name)).filter(|path| path.exists()))
+            .collect::<Vec<_>>();
+
+        let mut watched = vec![path.as_path(), Path::new("toy.frag"), Path::new(TOY_VERTEX)];
+        watched.extend(buffer_paths.iter().flatten().map(PathBuf::as_path));
+        let mut shader_watcher = shader::ShaderWatcher::new(&watched)?;
+
+        let pipeline = create_toy_pipeline(
+            &device,
+            &pipeline_layout,
+            sc_desc.format,
+            &mut shader_watcher,
+            &path,
+            false,
+        )?;
+
+        let mut inputs = channels::Inputs::default();
+        let channels = inputs.load_channels(&device, &queue, &path)?;
+
+        let mut buffers: [Option<Buffer>; CHANNELS] = Default::default();
+        for (buffer, path) in buffers.iter_mut().zip(buffer_paths) {
+            if let Some(path) = path {
+                let pipeline = create_toy_pipeline(
+                    &device,
+                    &pipeline_layout,
+                    BUFFER_FORMAT,
+                    &mut shader_watcher,
+                    &path,
+                    true,
+                )?;
+                let targets = Buffer::create_targets(&device, &queue, &sc_desc);
+                let channels = inputs.load_channels(&device, &queue, &path)?;
+                *buffer = Some(Buffer {
+                    path,
+                    pipeline,
+                    targets,
+                    current: 0,
+                    channels,
+                });
+            }
+        }
+
+        Ok(Self {
+            surface,
+            device,
+            queue,
+            sc_desc,
+            swap_chain,
+            path,
+            pipeline,
+            pipeline_layout,
+            uniforms,
+            uniform_buffer,
+            uniform_bind_group,
+            channel_bind_group_layout,
+            channel_sampler,
+            empty_channel,
+            buffers,
+            channels,
+            inputs,
+            shader_watcher,
+            mouse_pos: (0.0, 0.0).into(),
+            mouse_pressed: false,
+        })
+    }
+
+    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
+        self.sc_desc.width = new_size.width;
+        self.sc_desc.height = new_size.height;
+        self.swap_chain = self.device.create_swap_chain(&self.surface, &self.sc_desc);
+        for buffer in self.buffers.iter_mut().flatten() {
+            buffer.targets = Buffer::create_targets(&self.device, &self.queue, &self.sc_desc);
+        }
+        self.uniforms.iResolution = Vec3::new(new_size.width as f32, new_size.height as f32, 1.0);
+    }
+
+    /// Mouse position with Shadertoy's bottom left origin.
+    fn mouse(&self) -> (f32, f32) {
+        (
+            self.mouse_pos.x as f32,
+            self.sc_desc.height as f32 - self.mouse_pos.y as f32,
+        )
+    }
+
+    /// Follows Shadertoy's `iMouse`: xy is the position while the button is
+    /// held, zw where it was pressed. z is negated once it is released, w
+    /// after the frame it was pressed in.
+    pub fn input(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::MouseInput {
+                button: MouseButton::Left,
+                state,
+                ..
+            } => {
+                self.mouse_pressed = *state == ElementState::Pressed;
+                let (x, y) = self.mouse();
+                if self.mouse_pressed {
+                    self.uniforms.iMouse = Vec4::new(x, y, x, y);
+                } else {
+                    self.uniforms.iMouse.z = -self.uniforms.iMouse.z.abs();
+                }
+                true
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.mouse_pos = *position;
+                if self.mouse_pressed {
+                    let (x, y) = self.mouse();
+                    self.uniforms.iMouse.x = x;
+                    self.uniforms.iMouse.y = y;
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Rebuilds every pass when anything changed, since they share the
+    /// prelude. A pass that fails to compile keeps its last good pipeline.
+    fn reload_shaders(&mut self) {
+        if self.shader_watcher.poll().is_empty() {
+            return;
+        }
+        match create_toy_pipeline(
+            &self.device,
+            &self.pipeline_layout,
+            self.sc_desc.format,
+            &mut self.shader_watcher,
+            &self.path,
+            false,
+        ) {
+            Ok(pipeline) => {
+                log::info!("Reloaded {}", self.path.display());
+                self.pipeline = pipeline;
+            }
+            Err(e) => log::error!("Keeping the last good toy: {}", e),
+        }
+        for buffer in self.buffers.iter_mut().flatten() {
+            match create_toy_pipeline(
+                &self.device,
+                &self.pipeline_layout,
+                BUFFER_FORMAT,
+                &mut self.shader_watcher,
+                &buffer.path,
+                true,
+            ) {
+                Ok(pipeline) => {
+                    log::info!("Reloaded {}", buffer.path.display());
+                    buffer.pipeline = pipeline;
+                }
+                Err(e) => log::error!("Keeping the last good buffer: {}", e),
+            }
+        }
+    }
+
+    /// Binds what `channels` name. Buffers are read at their most recently
+    /// finished frame.
+    fn channel_bind_group(&self, channels: &[Channel; CHANNELS]) -> wgpu::BindGroup {
+        let views = channels
+            .iter()
+            .map(|&channel| match channel {
+                Channel::Buffer(index) => self.buffers[index]
+                    .as_ref()
+                    .map(|buffer| &buffer.targets[buffer.current].view),
+                channel => self.inputs.view(channel),
+            })
+            .map(|view| view.unwrap_or(&self.empty_channel.view))
+            .collect::<Vec<_>>();
+
+        let mut entries = views
+            .into_iter()
+            .enumerate()
+            .map(|(binding, view)| wgpu::BindGroupEntry {
+                binding: binding as u32,
+                resource: wgpu::BindingResource::TextureView(view),
+            })
+            .collect::<Vec<_>>();
+        entries.push(wgpu::BindGroupEntry {
+            binding: CHANNELS as u32,
+            resource: wgpu::BindingResource::Sampler(&self.channel_sampler),
+        });
+
+        self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.channel_bind_group_layout,
+            entries: &entries,
+            label: Some("toy_channel_bind_group"),
+        })
+    }
+
+    /// Draws Buffer A-D into its other target, which then becomes current
+    /// for the passes after it.
+    fn draw_buffer(&mut self, encoder: &mut wgpu::CommandEncoder, index: usize) {
+        if let Some(buffer) = &self.buffers[index] {
+            let channel_bind_group = self.channel_bind_group(&buffer.channels);
+            draw_pass(
+                encoder,
+                &buffer.targets[1 - buffer.current].view,
+                &buffer.pipeline,
+                &self.uniform_bind_group,
+                &channel_bind_group,
+            );
+        }
+        if let Some(buffer) = &mut self.buffers[index] {
+            buffer.current = 1 - buffer.current;
+        }
+    }
+
+    pub fn update(&mut self, dt: std::time::Duration) {
+        self.reload_shaders();
+        self.uniforms.iTimeDelta = dt.as_secs_f32();
+        self.uniforms.iTime += self.uniforms.iTimeDelta;
+        self.uniforms.iDate = date();
+        self.inputs.update(&self.queue);
+        self.queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[self.uniforms]),
+        );
+    }
+
+    pub fn render(&mut self) {
+        let frame = self
+            .swap_chain
+            .get_current_frame()
+            .expect("Timeout getting texture")
+            .output;
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Toy Encoder"),
+            });
+
+        for index in 0..CHANNELS {
+            self.draw_buffer(&mut encoder, index);
+        }
+
+        let channel_bind_group = self.channel_bind_group(&self.channels);
+        draw_pass(
+            &mut encoder,
+            &frame.view,
+            &self.pipeline,
+            &self.uniform_bind_group,
+            &channel_bind_group,
+        );
+
+        self.queue.submit(iter::once(encoder.finish()));
+
+        self.uniforms.iFrame += 1;
+        self.uniforms.iMouse.w = -self.uniforms.iMouse.w.abs();
+    }
+}
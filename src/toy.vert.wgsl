@@ -0,0 +1,12 @@
+# One triangle that covers the whole screen, generated without a vertex
+# buffer.
+[[builtin vertex_idx]] var<in> in_vertex_index : u32;
+[[builtin position]] var<out> o_position : vec4<f32>;
+
+fn main() -> void {
+  var x : f32 = f32((in_vertex_index << 1) & 2);
+  var y : f32 = f32(in_vertex_index & 2);
+  o_position = vec4<f32>(x * 2.0 - 1.0, y * 2.0 - 1.0, 0.0, 1.0);
+  return;
+}
+entry_point vertex as "main" = main;
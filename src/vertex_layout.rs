@@ -0,0 +1,33 @@
+//! Vertex attribute layouts. `build.rs` includes this file as well, to check
+//! the layouts against the inputs of the vertex shaders they feed.
+
+use std::mem;
+
+pub const MODEL_VERTEX_ATTRIBUTES: [wgpu::VertexAttributeDescriptor; 5] = [
+    wgpu::VertexAttributeDescriptor {
+        offset: 0,
+        shader_location: 0,
+        format: wgpu::VertexFormat::Float3,
+    },
+    wgpu::VertexAttributeDescriptor {
+        offset: mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+        shader_location: 1,
+        format: wgpu::VertexFormat::Float2,
+    },
+    wgpu::VertexAttributeDescriptor {
+        offset: mem::size_of::<[f32; 5]>() as wgpu::BufferAddress,
+        shader_location: 2,
+        format: wgpu::VertexFormat::Float3,
+    },
+    // Tangent and bitangent
+    wgpu::VertexAttributeDescriptor {
+        offset: mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+        shader_location: 3,
+        format: wgpu::VertexFormat::Float3,
+    },
+    wgpu::VertexAttributeDescriptor {
+        offset: mem::size_of::<[f32; 11]>() as wgpu::BufferAddress,
+        shader_location: 4,
+        format: wgpu::VertexFormat::Float3,
+    },
+];
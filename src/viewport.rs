@@ -0,0 +1,181 @@
+//! Splitting the window into several views of the scene, each with its own
+//! camera and optionally its own shading, to compare them side by side.
+
+use wgpu::util::DeviceExt;
+
+use crate::camera::{Camera, FreeCamera, Projection};
+use crate::{permutations, Mat4, Uniforms, Vec3};
+
+/// Pixels, with the origin at the top left of the window.
+#[derive(Debug, Copy, Clone)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x as f64
+            && y >= self.y as f64
+            && x < (self.x + self.width) as f64
+            && y < (self.y + self.height) as f64
+    }
+}
+
+/// How the window is divided between viewports.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Layout {
+    Single,
+    SideBySide,
+    Grid,
+}
+
+impl Layout {
+    /// The most viewports any layout has. `instances.comp` has room for
+    /// this many instance counts.
+    pub const MAX_VIEWPORTS: usize = 4;
+
+    pub fn next(self) -> Self {
+        match self {
+            Layout::Single => Layout::SideBySide,
+            Layout::SideBySide => Layout::Grid,
+            Layout::Grid => Layout::Single,
+        }
+    }
+
+    fn columns_rows(self) -> (u32, u32) {
+        match self {
+            Layout::Single => (1, 1),
+            Layout::SideBySide => (2, 1),
+            Layout::Grid => (2, 2),
+        }
+    }
+
+    pub fn count(self) -> usize {
+        let (columns, rows) = self.columns_rows();
+        (columns * rows) as usize
+    }
+
+    /// Where viewport `index` goes in a window of the given size. Viewports
+    /// are numbered left to right, then top to bottom.
+    pub fn rect(self, index: usize, width: u32, height: u32) -> Rect {
+        let (columns, rows) = self.columns_rows();
+        let (column, row) = (index as u32 % columns, index as u32 / columns);
+        // Cells take the leftover pixels of sizes that don't divide evenly
+        let x = column * width / columns;
+        let y = row * height / rows;
+        Rect {
+            x,
+            y,
+            width: (column + 1) * width / columns - x,
+            height: (row + 1) * height / rows - y,
+        }
+    }
+}
+
+/// One view of the scene, with the uniforms its draws are made with.
+pub struct Viewport {
+    pub camera: Camera,
+    /// Flown instead of `camera` while it is set. `camera` keeps following
+    /// it, minus the roll, for everything that only knows about yaw and
+    /// pitch, like bookmarks and camera paths.
+    pub free_camera: Option<FreeCamera>,
+    pub projection: Projection,
+    pub rect: Rect,
+    /// The variant of `shader.frag` this viewport is shaded with instead of
+    /// the one the main pipeline uses, and the pipeline built from it.
+    pub shader_frag: Option<permutations::ShaderFrag>,
+    pub pipeline: Option<wgpu::RenderPipeline>,
+    pub uniforms: Uniforms,
+    pub uniform_buffer: wgpu::Buffer,
+    pub uniform_bind_group: wgpu::BindGroup,
+    /// Where the instances culled for this viewport start in the instance
+    /// buffer, and how many there are.
+    pub first_instance: u32,
+    pub visible_instances: u32,
+}
+
+impl Viewport {
+    pub fn new(
+        device: &wgpu::Device,
+        uniform_bind_group_layout: &wgpu::BindGroupLayout,
+        instance_buffer: &wgpu::Buffer,
+        camera: Camera,
+        mut projection: Projection,
+        rect: Rect,
+        first_instance: u32,
+    ) -> Self {
+        projection.resize(rect.width, rect.height);
+        let mut uniforms = Uniforms::new(Vec3::zero(), Mat4::from_scale_4d(0.), -1);
+        uniforms.update_view_proj(&camera, &projection);
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[uniforms]),
+            usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+        });
+
+        let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: uniform_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(uniform_buffer.slice(..)),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Buffer(instance_buffer.slice(..)),
+                },
+            ],
+            label: Some("uniform_bind_group"),
+        });
+
+        Self {
+            camera,
+            free_camera: None,
+            projection,
+            rect,
+            shader_frag: None,
+            pipeline: None,
+            uniforms,
+            uniform_buffer,
+            uniform_bind_group,
+            first_instance,
+            visible_instances: 0,
+        }
+    }
+
+    pub fn resize(&mut self, rect: Rect) {
+        self.rect = rect;
+        self.projection.resize(rect.width, rect.height);
+    }
+
+    /// Brings the free camera, when there is one, to wherever something
+    /// other than its controller has moved `camera`. It comes out level.
+    pub fn follow_camera(&mut self) {
+        if let Some(free_camera) = &mut self.free_camera {
+            *free_camera = FreeCamera::from_camera(&self.camera);
+        }
+    }
+
+    pub fn update_view_proj(&mut self) {
+        match &self.free_camera {
+            Some(free_camera) => self
+                .uniforms
+                .update_view_proj(free_camera, &self.projection),
+            None => self
+                .uniforms
+                .update_view_proj(&self.camera, &self.projection),
+        }
+    }
+
+    pub fn write_uniforms(&self, queue: &wgpu::Queue) {
+        queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[self.uniforms]),
+        );
+    }
+}
@@ -0,0 +1,23 @@
+//! WGSL checks shared by `build.rs` and the runtime shader reloader. wgpu
+//! takes WGSL as source and panics on anything it can't parse, so both sides
+//! run it through naga first.
+
+use anyhow::*;
+use std::path::Path;
+
+/// Parses and validates `src`, read from `path`. Like the GLSL shaders it
+/// needs an entry point named `main`.
+pub fn validate(path: &Path, src: &str) -> Result<()> {
+    // naga's parse errors borrow the source and have no `Display`
+    let module =
+        naga::front::wgsl::parse_str(src).map_err(|e| anyhow!("{}: {:?}", path.display(), e))?;
+    naga::proc::Validator::new()
+        .validate(&module)
+        .with_context(|| format!("{} is invalid", path.display()))?;
+    ensure!(
+        module.entry_points.iter().any(|entry| entry.name == "main"),
+        "{} has no entry point named \"main\"",
+        path.display()
+    );
+    Ok(())
+}